@@ -1,11 +1,11 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Owner {
     pub name: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct LockEntry {
     pub id: String,
     pub path: String,
@@ -13,58 +13,495 @@ pub struct LockEntry {
     pub locked_at: String,
 }
 
-#[cfg(target_arch = "wasm32")]
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "cmd", rename_all = "camelCase")]
-pub enum Request {
-    Echo { message: String },
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub path: String,
+    pub score: i64,
+    /// Char indices of `path` matched by the filter.
+    pub indices: Vec<usize>,
+    pub lfs: bool,
+    /// Whether the `lockable` attribute is set for the path.
+    pub lockable: bool,
+    pub lock: Option<LockEntry>,
+}
+
+/// A folder or file of the directory tree.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TreeNode {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    /// Lfs files below the folder, 1 for a file.
+    pub files: usize,
+    pub locked: usize,
+    /// Lock owners below the folder and how many files each holds, most first.
+    pub owners: Vec<(String, usize)>,
+    pub lock: Option<LockEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// Outcome of a lock or unlock over several files.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct BatchResult {
+    pub done: Vec<String>,
+    pub failed: Vec<BatchFailure>,
+}
+
+/// The lfs server could not be reached, so the lock list is the last one fetched.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Offline {
+    /// When the listed locks were fetched, RFC 3339. `None` if none were fetched yet
+    /// and only our own locks known to git lfs locally are listed.
+    pub since: Option<String>,
+    /// Why the server could not be reached.
+    pub error: String,
+}
+
+/// A lock or unlock asked for while the lfs server could not be reached, done once it
+/// can be.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PendingOperation {
+    pub path: String,
+    /// Whether `path` is to be locked or unlocked.
+    pub lock: bool,
+    /// Id of the lock to release, for unlocks.
+    pub id: Option<String>,
+    /// When it was asked for, RFC 3339.
+    pub time: String,
+}
+
+/// A watched file was locked or unlocked, or the app locked or unlocked a file for us.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WatchEvent {
+    pub path: String,
+    /// Owner of the lock that was taken or released.
+    pub owner: String,
+    pub locked: bool,
+    /// Done by the app, locking a queued file or unlocking a pushed one.
+    #[serde(default)]
+    pub automatic: bool,
+    /// When the change was noticed, RFC 3339.
+    pub time: String,
+}
+
+impl WatchEvent {
+    pub fn describe(&self) -> String {
+        match (self.automatic, self.locked) {
+            (true, true) => format!("{} is free and now locked by you", self.path),
+            (true, false) => format!("{} was pushed and is now unlocked", self.path),
+            (false, true) => format!("{} locked {}", self.owner, self.path),
+            (false, false) => format!("{} unlocked {}", self.owner, self.path),
+        }
+    }
+}
+
+/// One of our locks about to be released.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnlockPreview {
+    pub lock: LockEntry,
+    /// The file has uncommitted changes.
+    pub modified: bool,
+}
+
+/// Lock numbers of a repo, for the dashboard and `--stats`. Ages are in seconds and
+/// leave out locks without a readable time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct LockStats {
+    pub total: usize,
+    /// Lock counts by owner, most first.
+    pub per_owner: Vec<(String, usize)>,
+    /// Lock counts by directory a few levels deep, most first. Files at the root
+    /// count for "".
+    pub per_directory: Vec<(String, usize)>,
+    pub median_age: Option<i64>,
+    pub oldest: Option<LockEntry>,
+    pub longest_age: Option<i64>,
+    pub stale_days: u32,
+    /// Locks older than `stale_days`, oldest first.
+    pub stale: Vec<LockEntry>,
+}
+
+/// File formats locks can be exported to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+    /// A standalone page, styles included.
+    Html,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Markdown,
+        ExportFormat::Html,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// Name of the webview event carrying an `Event`.
+pub const EVENT_NAME: &str = "backend-event";
+
+/// Pushed by the backend without a request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum Event {
+    /// Someone took a lock, noticed by a refresh or a lock of our own.
+    LockAdded {
+        lock: LockEntry,
+    },
+    LockRemoved {
+        lock: LockEntry,
+    },
+    /// Another repo was picked.
+    RepoChanged {
+        repo: String,
+    },
+    /// The list of files to search was read again.
+    CacheRebuilt {
+        files: usize,
+    },
+    /// A lock or unlock over several files went one file further.
+    JobProgress {
+        job: String,
+        done: usize,
+        total: usize,
+    },
+    /// Something failed in the background, outside of any request.
+    Error {
+        message: String,
+    },
+    /// Git or ssh asks for a user name, password or passphrase, answered with
+    /// `Request::AnswerPrompt`. `secret` if the answer should not be shown.
+    CredentialPrompt {
+        id: u32,
+        prompt: String,
+        secret: bool,
+    },
+    /// Prompt `id` was answered, cancelled or timed out.
+    PromptClosed {
+        id: u32,
+    },
+    /// The lfs server became unreachable, or reachable again with `offline: None`.
+    ConnectionChanged {
+        offline: Option<Offline>,
+    },
+    /// The locks and unlocks asked for while offline were done, or failed as someone
+    /// else held the file meanwhile.
+    PendingReplayed {
+        result: BatchResult,
+    },
+}
+
+/// Something the user can do from the keyboard or the command palette.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Command {
+    OpenPalette,
+    FocusSearch,
+    SelectNext,
+    SelectPrevious,
+    /// Locks the selected file, or unlocks it if it is locked.
+    ToggleLock,
+    Refresh,
     PickRepo,
-    GetLockedFiles,
-    GetFilteredFiles { filter: String },
-    LockFile { path: String },
-    UnlockFile { id: u32 },
+    ToggleTreeView,
+    ToggleInbox,
+    UnlockAllMine,
+    ExportLocks,
+    ToggleStats,
+}
+
+impl Command {
+    pub const ALL: [Command; 12] = [
+        Command::OpenPalette,
+        Command::FocusSearch,
+        Command::SelectNext,
+        Command::SelectPrevious,
+        Command::ToggleLock,
+        Command::Refresh,
+        Command::PickRepo,
+        Command::ToggleTreeView,
+        Command::ToggleInbox,
+        Command::UnlockAllMine,
+        Command::ExportLocks,
+        Command::ToggleStats,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Command::OpenPalette => "Open command palette",
+            Command::FocusSearch => "Focus search",
+            Command::SelectNext => "Select next row",
+            Command::SelectPrevious => "Select previous row",
+            Command::ToggleLock => "Lock or unlock selected file",
+            Command::Refresh => "Refresh locked files",
+            Command::PickRepo => "Switch repo",
+            Command::ToggleTreeView => "Toggle tree view",
+            Command::ToggleInbox => "Toggle inbox",
+            Command::UnlockAllMine => "Unlock all mine",
+            Command::ExportLocks => "Export locks",
+            Command::ToggleStats => "Toggle lock statistics",
+        }
+    }
+
+    /// Key binding unless the settings change it, empty for palette only commands.
+    pub fn default_keys(&self) -> &'static str {
+        match self {
+            Command::OpenPalette => "Ctrl+K",
+            Command::FocusSearch => "/",
+            Command::SelectNext => "ArrowDown",
+            Command::SelectPrevious => "ArrowUp",
+            Command::ToggleLock => "Ctrl+L",
+            Command::Refresh => "F5",
+            Command::PickRepo => "Ctrl+O",
+            Command::ToggleTreeView => "Ctrl+T",
+            Command::ToggleInbox => "Ctrl+I",
+            Command::UnlockAllMine => "",
+            Command::ExportLocks => "",
+            Command::ToggleStats => "",
+        }
+    }
+}
+
+/// Protocol spoken by this build, bumped on incompatible changes to `Request` or
+/// `Response`.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Oldest protocol the backend still answers.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// A request or response on the wire. A response carries the id of its request.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Envelope<T> {
+    pub version: u32,
+    pub id: u64,
+    pub payload: T,
 }
 
-#[cfg(not(target_arch = "wasm32"))]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "cmd", rename_all = "camelCase")]
 pub enum Request {
+    /// First request of a client, `version` is the newest protocol it speaks. The
+    /// answer has the version both sides use and the current repo.
+    Hello {
+        version: u32,
+    },
     Echo {
         message: String,
-        callback: String,
-        error: String,
-    },
-    PickRepo {
-        callback: String,
-        error: String,
     },
+    PickRepo,
+    /// Locks sorted by path. `limit: None` returns everything from `offset`,
+    /// `cached` reuses the list of the previous request instead of asking the
+    /// server again.
     GetLockedFiles {
-        callback: String,
-        error: String,
+        #[serde(default)]
+        offset: usize,
+        #[serde(default)]
+        limit: Option<usize>,
+        #[serde(default)]
+        cached: bool,
     },
+    /// Searches lfs objects, or every tracked file with `all_files`.
     GetFilteredFiles {
         filter: String,
-        callback: String,
-        error: String,
+        #[serde(default)]
+        offset: usize,
+        #[serde(default)]
+        limit: Option<usize>,
+        #[serde(default)]
+        all_files: bool,
     },
     LockFile {
         path: String,
-        callback: String,
-        error: String,
     },
     UnlockFile {
         id: u32,
-        callback: String,
-        error: String,
+    },
+    /// Pinned and recently locked or unlocked files of the current repo.
+    GetQuickAccess,
+    PinFile {
+        path: String,
+        pinned: bool,
+    },
+    /// Folders and files directly in `dir`, empty for the repo root.
+    GetTree {
+        #[serde(default)]
+        dir: String,
+    },
+    /// Locks every unlocked lfs file below `dir`.
+    LockFolder {
+        dir: String,
+    },
+    /// Releases our own locks below `dir`.
+    UnlockFolder {
+        dir: String,
+    },
+    /// Our own locks, checked with the server, and which of them have local changes.
+    PreviewUnlockMine,
+    /// Releases the given locks, refusing those of other users.
+    UnlockMine {
+        ids: Vec<u32>,
+    },
+    WatchFile {
+        path: String,
+        watched: bool,
+    },
+    /// Waits for `path` to be unlocked and locks it, or stops waiting.
+    QueueLock {
+        path: String,
+        queued: bool,
+    },
+    /// Our locks whose changes are pushed, unlocked right away if the release
+    /// mode is `auto`.
+    CheckPushedLocks,
+    /// Lock changes of watched files noticed so far.
+    GetInbox,
+    ClearInbox,
+    /// Keys of every command, the defaults with the settings applied.
+    GetKeyBindings,
+    /// Locale of the interface from the settings, empty for the system's.
+    GetLocale,
+    /// Writes the locks matching `filter` to `path`, or to a file picked in a save
+    /// dialog if it is `None`. Over the http api `path` must be `None` and the
+    /// export is answered in `contents` instead.
+    ExportLocks {
+        filter: String,
+        format: ExportFormat,
+        path: Option<String>,
+    },
+    /// Statistics of the current locks, `stale_days: None` takes the settings'.
+    GetLockStats {
+        stale_days: Option<u32>,
+    },
+    /// Answers an `Event::CredentialPrompt`, `answer: None` cancels it.
+    AnswerPrompt {
+        id: u32,
+        answer: Option<String>,
+    },
+    /// Drops the lock or unlock of `path` waiting for the server.
+    CancelPending {
+        path: String,
     },
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub enum Response {
-    PickRepo { path: String },
-    GetLockedFiles { locked_files: Vec<String> },
-    GetFilteredFiles { filtered_files: Vec<String> },
-    LockFile { lock_entry: LockEntry },
-    UnlockFile { id: u32 },
+    Hello {
+        version: u32,
+        repo: String,
+    },
+    Echo {
+        message: String,
+    },
+    PickRepo {
+        path: String,
+    },
+    GetLockedFiles {
+        locked_files: Vec<LockEntry>,
+        total: usize,
+        /// Set if the server could not be reached and the locks are the last known.
+        #[serde(default)]
+        offline: Option<Offline>,
+    },
+    GetFilteredFiles {
+        filtered_files: Vec<SearchResult>,
+        total: usize,
+    },
+    LockFile {
+        lock_entry: LockEntry,
+    },
+    UnlockFile {
+        id: u32,
+    },
+    GetQuickAccess {
+        pinned: Vec<SearchResult>,
+        recent: Vec<SearchResult>,
+        watched: Vec<SearchResult>,
+        /// Files waiting to be locked for us.
+        queued: Vec<SearchResult>,
+        /// Locks and unlocks waiting for the server, oldest first.
+        #[serde(default)]
+        pending: Vec<PendingOperation>,
+    },
+    PinFile {
+        path: String,
+        pinned: bool,
+    },
+    GetTree {
+        dir: String,
+        nodes: Vec<TreeNode>,
+    },
+    LockFolder {
+        result: BatchResult,
+    },
+    UnlockFolder {
+        result: BatchResult,
+    },
+    PreviewUnlockMine {
+        locks: Vec<UnlockPreview>,
+    },
+    UnlockMine {
+        result: BatchResult,
+    },
+    WatchFile {
+        path: String,
+        watched: bool,
+    },
+    QueueLock {
+        path: String,
+        queued: bool,
+    },
+    CheckPushedLocks {
+        /// Pushed locks waiting for confirmation to unlock.
+        pending: Vec<LockEntry>,
+        released: BatchResult,
+    },
+    GetInbox {
+        events: Vec<WatchEvent>,
+    },
+    ClearInbox,
+    GetKeyBindings {
+        bindings: Vec<(Command, String)>,
+    },
+    GetLocale {
+        locale: String,
+    },
+    /// `path: None` if the save dialog was cancelled.
+    ExportLocks {
+        path: Option<String>,
+        count: usize,
+        #[serde(default)]
+        contents: Option<String>,
+    },
+    GetLockStats {
+        stats: LockStats,
+    },
+    AnswerPrompt {
+        id: u32,
+    },
+    /// Answers `LockFile` or `UnlockFile` while the server can't be reached, the
+    /// operation is done once it can.
+    Pending {
+        operation: PendingOperation,
+    },
+    CancelPending {
+        path: String,
+    },
 }
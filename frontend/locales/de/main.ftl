@@ -0,0 +1,172 @@
+## Obere Leiste
+
+pick-repo = Repo wählen
+force-refresh = Sperren neu laden
+list-view = Listenansicht
+tree-view = Baumansicht
+inbox = Posteingang ({ $unread })
+search-placeholder = Suchen, z. B. hero ext:uasset owner:alice locked:no
+all-files = Alle Dateien
+unlock-all-mine = Alle meine entsperren
+
+## Dateiliste
+
+column-file = Dateiname
+column-locked-by = Gesperrt von
+column-action = Aktion
+lock = Sperren
+unlock = Entsperren
+lock-all = Alle sperren
+unlock-mine = Meine entsperren
+folder-locked = { $locked }/{ $files } gesperrt
+locked-since = Gesperrt am { $date }
+pin = Anheften
+unpin = Lösen
+watch = Sperränderungen beobachten
+stop-watching = Nicht mehr beobachten
+queue = Anstellen
+queue-title = Für mich sperren, sobald sie frei ist
+cancel-queue = Nicht mehr anstellen
+cancel-queue-title = Warteschlange verlassen
+tag-lfs = LFS
+tag-not-lfs = kein LFS
+tag-lockable = sperrbar
+tag-not-lockable = nicht sperrbar
+section-pinned = Angeheftet
+section-recent = Zuletzt
+section-watching = Beobachtet
+section-queued = Angestellt
+section-pending = Wartet auf den Server
+pending-lock = Sperren angefordert um { $time }
+pending-unlock = Entsperren angefordert um { $time }
+cancel-pending = Abbrechen
+
+## Berichte und Dialoge
+
+batch-report = { $done } erledigt, { $failed } fehlgeschlagen
+job-progress = { $job }: { $done } von { $total }
+offline-since = Offline — Daten von { $time }
+offline-local = Offline — nur deine lokal bekannten Sperren werden angezeigt
+inbox-clear = Leeren
+inbox-empty = Nichts Neues bei beobachteten Dateien.
+inbox-locked = { $owner } hat { $path } gesperrt
+inbox-unlocked = { $owner } hat { $path } entsperrt
+inbox-acquired = { $path } ist frei und jetzt von dir gesperrt
+inbox-released = { $path } wurde gepusht und ist jetzt entsperrt
+unlock-preview =
+    { $count ->
+        [one] Deine Sperre aufheben?
+       *[other] { $count } deiner Sperren aufheben?
+    }
+unlock-preview-modified =
+    { $count ->
+        [one] Eine dieser Dateien hat nicht committete Änderungen, andere können sie nach dem Entsperren überschreiben.
+       *[other] { $count } dieser Dateien haben nicht committete Änderungen, andere können sie nach dem Entsperren überschreiben.
+    }
+tag-modified = geändert
+cancel = Abbrechen
+release-pushed =
+    { $count ->
+        [one] Deine Änderungen an einer gesperrten Datei sind gepusht, entsperren?
+       *[other] Deine Änderungen an { $count } gesperrten Dateien sind gepusht, entsperren?
+    }
+keep-locked = Gesperrt lassen
+prompt-title = Git fragt nach
+prompt-ok = OK
+
+## Befehlspalette
+
+palette-placeholder = Befehl eingeben
+close = Schließen
+command-openPalette = Befehlspalette öffnen
+command-focusSearch = Zur Suche
+command-selectNext = Nächste Zeile wählen
+command-selectPrevious = Vorige Zeile wählen
+command-toggleLock = Gewählte Datei sperren oder entsperren
+command-refresh = Sperren neu laden
+command-pickRepo = Repo wechseln
+command-toggleTreeView = Baumansicht umschalten
+command-toggleInbox = Posteingang umschalten
+command-unlockAllMine = Alle meine entsperren
+command-exportLocks = Sperren exportieren
+command-toggleStats = Statistik umschalten
+
+## Export
+
+export = Exportieren
+export-title = Sperren exportieren
+export-all = Alle aktuellen Sperren, mit Besitzer, Alter und lokalem Status.
+export-filtered = Die Sperren passend zu „{ $filter }“, mit Besitzer, Alter und lokalem Status.
+export-csv = CSV
+export-json = JSON
+export-md = Markdown
+export-html = HTML
+export-done =
+    { $count ->
+        [one] Eine Sperre nach { $path } gespeichert
+       *[other] { $count } Sperren nach { $path } gespeichert
+    }
+
+## Statistik
+
+stats = Statistik
+stats-total =
+    { $count ->
+        [one] Eine Sperre
+       *[other] { $count } Sperren
+    }
+stats-median = Mittleres Alter { $age }
+stats-longest = Am längsten { $age } von { $owner }, { $path }
+stats-per-owner = Sperren pro Besitzer
+stats-per-directory = Sperren pro Ordner
+stats-stale =
+    { $count ->
+        [one] Eine Sperre älter als
+       *[other] { $count } Sperren älter als
+    }
+stats-days = Tage
+duration-days =
+    { $count ->
+        [one] 1 Tag
+       *[other] { $count } Tage
+    }
+duration-hours =
+    { $count ->
+        [one] 1 Stunde
+       *[other] { $count } Stunden
+    }
+duration-minutes =
+    { $count ->
+        [one] 1 Minute
+       *[other] { $count } Minuten
+    }
+
+## Fehler
+
+failed = { $action } fehlgeschlagen, { $error }
+failed-unexpected = { $action } fehlgeschlagen, unerwartete Antwort { $response }
+invalid-lock-id = der Server hat der Sperre die Id { $id } gegeben, die keine Zahl ist
+action-connect = Verbinden mit dem Backend
+action-get-locked-files = Laden der Sperren
+action-search = Suche
+action-change-folder-locks = Ändern der Ordnersperren
+action-pick-repo = Wählen des Repos
+action-get-locale = Laden der Sprache
+action-lock = Sperren von { $path }
+action-unlock = Entsperren von { $path }
+action-list-your-locks = Auflisten deiner Sperren
+action-unlock-your-locks = Entsperren deiner Sperren
+action-get-lock-statistics = Laden der Sperrstatistik
+action-export-locks = Exportieren der Sperren
+action-answer-prompt = Beantworten der Abfrage
+action-check-for-pushed-locks = Prüfen auf gepushte Sperren
+action-unlock-pushed-locks = Entsperren gepushter Sperren
+action-get-pinned-and-recent-files = Laden der angehefteten und letzten Dateien
+action-list-folder = Auflisten des Ordners
+action-pin-file = Anheften der Datei
+action-watch-file = Beobachten der Datei
+action-queue-lock = Einreihen der Sperre
+action-cancel-pending-operation = Abbrechen des wartenden Vorgangs
+action-get-inbox = Laden des Posteingangs
+action-clear-inbox = Leeren des Posteingangs
+action-get-key-bindings = Laden der Tastenbelegung
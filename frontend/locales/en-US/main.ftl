@@ -0,0 +1,175 @@
+# Texts of the interface. Ids are shared by every locale, missing ones fall back to
+# this file.
+
+## Top bar
+
+pick-repo = Pick Repo
+force-refresh = Force Refresh Locked Files
+list-view = List View
+tree-view = Tree View
+inbox = Inbox ({ $unread })
+search-placeholder = Search, e.g. hero ext:uasset owner:alice locked:no
+all-files = All files
+unlock-all-mine = Unlock All Mine
+
+## File list
+
+column-file = File Name
+column-locked-by = Locked By
+column-action = Action
+lock = Lock
+unlock = Unlock
+lock-all = Lock all
+unlock-mine = Unlock mine
+folder-locked = { $locked }/{ $files } locked
+locked-since = Locked { $date }
+pin = Pin
+unpin = Unpin
+watch = Watch lock changes
+stop-watching = Stop watching
+queue = Queue
+queue-title = Lock it for me once it is free
+cancel-queue = Cancel queue
+cancel-queue-title = Leave the queue
+tag-lfs = LFS
+tag-not-lfs = not LFS
+tag-lockable = lockable
+tag-not-lockable = not lockable
+section-pinned = Pinned
+section-recent = Recent
+section-watching = Watching
+section-queued = Queued
+section-pending = Waiting for the server
+pending-lock = Lock asked for at { $time }
+pending-unlock = Unlock asked for at { $time }
+cancel-pending = Cancel
+
+## Reports and dialogs
+
+batch-report = { $done } done, { $failed } failed
+job-progress = { $job }: { $done } of { $total }
+offline-since = Offline — data from { $time }
+offline-local = Offline — only your own locks known locally are listed
+inbox-clear = Clear
+inbox-empty = Nothing new on watched files.
+inbox-locked = { $owner } locked { $path }
+inbox-unlocked = { $owner } unlocked { $path }
+inbox-acquired = { $path } is free and now locked by you
+inbox-released = { $path } was pushed and is now unlocked
+unlock-preview =
+    { $count ->
+        [one] Unlock your lock?
+       *[other] Unlock { $count } of your locks?
+    }
+unlock-preview-modified =
+    { $count ->
+        [one] One of these files has uncommitted changes, others can overwrite it once unlocked.
+       *[other] { $count } of these files have uncommitted changes, others can overwrite them once unlocked.
+    }
+tag-modified = modified
+cancel = Cancel
+release-pushed =
+    { $count ->
+        [one] Your changes to a locked file are pushed, unlock it?
+       *[other] Your changes to { $count } locked files are pushed, unlock them?
+    }
+keep-locked = Keep Locked
+prompt-title = Git is asking
+prompt-ok = OK
+
+## Command palette
+
+palette-placeholder = Type a command
+close = Close
+command-openPalette = Open command palette
+command-focusSearch = Focus search
+command-selectNext = Select next row
+command-selectPrevious = Select previous row
+command-toggleLock = Lock or unlock selected file
+command-refresh = Refresh locked files
+command-pickRepo = Switch repo
+command-toggleTreeView = Toggle tree view
+command-toggleInbox = Toggle inbox
+command-unlockAllMine = Unlock all mine
+command-exportLocks = Export locks
+command-toggleStats = Toggle lock statistics
+
+## Export
+
+export = Export
+export-title = Export locks
+export-all = Every current lock, with owner, age and local status.
+export-filtered = The locks matching “{ $filter }”, with owner, age and local status.
+export-csv = CSV
+export-json = JSON
+export-md = Markdown
+export-html = HTML
+export-done =
+    { $count ->
+        [one] Saved one lock to { $path }
+       *[other] Saved { $count } locks to { $path }
+    }
+
+## Statistics
+
+stats = Stats
+stats-total =
+    { $count ->
+        [one] One lock
+       *[other] { $count } locks
+    }
+stats-median = Median age { $age }
+stats-longest = Longest { $age } by { $owner }, { $path }
+stats-per-owner = Locks per owner
+stats-per-directory = Locks per directory
+stats-stale =
+    { $count ->
+        [one] One lock older than
+       *[other] { $count } locks older than
+    }
+stats-days = days
+duration-days =
+    { $count ->
+        [one] 1 day
+       *[other] { $count } days
+    }
+duration-hours =
+    { $count ->
+        [one] 1 hour
+       *[other] { $count } hours
+    }
+duration-minutes =
+    { $count ->
+        [one] 1 minute
+       *[other] { $count } minutes
+    }
+
+## Failures
+
+failed = failed to { $action }, { $error }
+failed-unexpected = failed to { $action }, unexpected response { $response }
+invalid-lock-id = the server gave the lock the id { $id }, which is not a number
+action-connect = connect to the backend
+action-get-locked-files = get locked files
+action-search = search
+action-change-folder-locks = change folder locks
+action-pick-repo = pick repo
+action-get-locale = get locale
+action-lock = lock { $path }
+action-unlock = unlock { $path }
+action-list-your-locks = list your locks
+action-unlock-your-locks = unlock your locks
+action-get-lock-statistics = get lock statistics
+action-export-locks = export locks
+action-answer-prompt = answer prompt
+action-check-for-pushed-locks = check for pushed locks
+action-unlock-pushed-locks = unlock pushed locks
+action-get-pinned-and-recent-files = get pinned and recent files
+action-list-folder = list folder
+action-pin-file = pin file
+action-watch-file = watch file
+action-queue-lock = queue lock
+action-cancel-pending-operation = cancel pending operation
+action-get-inbox = get inbox
+action-clear-inbox = clear inbox
+action-get-key-bindings = get key bindings
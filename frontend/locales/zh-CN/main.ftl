@@ -0,0 +1,136 @@
+## 顶栏
+
+pick-repo = 选择仓库
+force-refresh = 强制刷新锁定文件
+list-view = 列表视图
+tree-view = 树状视图
+inbox = 收件箱 ({ $unread })
+search-placeholder = 搜索，例如 hero ext:uasset owner:alice locked:no
+all-files = 所有文件
+unlock-all-mine = 解锁我的全部文件
+
+## 文件列表
+
+column-file = 文件名
+column-locked-by = 锁定者
+column-action = 操作
+lock = 锁定
+unlock = 解锁
+lock-all = 全部锁定
+unlock-mine = 解锁我的
+folder-locked = 已锁定 { $locked }/{ $files }
+locked-since = 锁定于 { $date }
+pin = 置顶
+unpin = 取消置顶
+watch = 关注锁定变化
+stop-watching = 取消关注
+queue = 排队
+queue-title = 文件空闲后为我锁定
+cancel-queue = 取消排队
+cancel-queue-title = 退出队列
+tag-lfs = LFS
+tag-not-lfs = 非 LFS
+tag-lockable = 可锁定
+tag-not-lockable = 不可锁定
+section-pinned = 已置顶
+section-recent = 最近
+section-watching = 关注中
+section-queued = 排队中
+section-pending = 等待服务器
+pending-lock = 于 { $time } 请求锁定
+pending-unlock = 于 { $time } 请求解锁
+cancel-pending = 取消
+
+## 报告与对话框
+
+batch-report = 完成 { $done } 个，失败 { $failed } 个
+job-progress = { $job }：{ $done } / { $total }
+offline-since = 离线 — 数据来自 { $time }
+offline-local = 离线 — 仅显示本地已知的你的锁
+inbox-clear = 清空
+inbox-empty = 关注的文件没有新动态。
+inbox-locked = { $owner } 锁定了 { $path }
+inbox-unlocked = { $owner } 解锁了 { $path }
+inbox-acquired = { $path } 已空闲，现已为你锁定
+inbox-released = { $path } 已推送，现已解锁
+unlock-preview = 解锁你的 { $count } 个锁定？
+unlock-preview-modified = 其中 { $count } 个文件有未提交的修改，解锁后可能被他人覆盖。
+tag-modified = 已修改
+cancel = 取消
+release-pushed = 你对 { $count } 个锁定文件的修改已推送，是否解锁？
+keep-locked = 保持锁定
+prompt-title = Git 请求输入
+prompt-ok = 确定
+
+## 命令面板
+
+palette-placeholder = 输入命令
+close = 关闭
+command-openPalette = 打开命令面板
+command-focusSearch = 聚焦搜索框
+command-selectNext = 选择下一行
+command-selectPrevious = 选择上一行
+command-toggleLock = 锁定或解锁所选文件
+command-refresh = 刷新锁定文件
+command-pickRepo = 切换仓库
+command-toggleTreeView = 切换树状视图
+command-toggleInbox = 切换收件箱
+command-unlockAllMine = 解锁我的全部文件
+command-exportLocks = 导出锁定
+command-toggleStats = 切换锁定统计
+
+## 导出
+
+export = 导出
+export-title = 导出锁定
+export-all = 当前所有锁定，包括锁定者、时长和本地状态。
+export-filtered = 匹配“{ $filter }”的锁定，包括锁定者、时长和本地状态。
+export-csv = CSV
+export-json = JSON
+export-md = Markdown
+export-html = HTML
+export-done = 已将 { $count } 个锁定保存到 { $path }
+
+## 统计
+
+stats = 统计
+stats-total = 共 { $count } 个锁定
+stats-median = 锁定时长中位数 { $age }
+stats-longest = 最长 { $age }，{ $owner } 锁定的 { $path }
+stats-per-owner = 按锁定者
+stats-per-directory = 按目录
+stats-stale = { $count } 个锁定超过
+stats-days = 天
+duration-days = { $count } 天
+duration-hours = { $count } 小时
+duration-minutes = { $count } 分钟
+
+## 失败
+
+failed = { $action }失败：{ $error }
+failed-unexpected = { $action }失败：意外的响应 { $response }
+invalid-lock-id = 服务器给出的锁定 id { $id } 不是数字
+action-connect = 连接后端
+action-get-locked-files = 获取锁定文件
+action-search = 搜索
+action-change-folder-locks = 更改文件夹锁定
+action-pick-repo = 选择仓库
+action-get-locale = 获取语言
+action-lock = 锁定 { $path }
+action-unlock = 解锁 { $path }
+action-list-your-locks = 列出你的锁定
+action-unlock-your-locks = 解锁你的锁定
+action-get-lock-statistics = 获取锁定统计
+action-export-locks = 导出锁定
+action-answer-prompt = 回答提示
+action-check-for-pushed-locks = 检查已推送的锁定
+action-unlock-pushed-locks = 解锁已推送的锁定
+action-get-pinned-and-recent-files = 获取置顶和最近的文件
+action-list-folder = 列出文件夹
+action-pin-file = 置顶文件
+action-watch-file = 关注文件
+action-queue-lock = 排队锁定
+action-cancel-pending-operation = 取消等待中的操作
+action-get-inbox = 获取收件箱
+action-clear-inbox = 清空收件箱
+action-get-key-bindings = 获取快捷键
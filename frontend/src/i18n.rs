@@ -0,0 +1,180 @@
+//! Translated texts and locale aware numbers and dates. The catalogs are Fluent files
+//! under `locales/`, compiled into the frontend.
+
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource, FluentValue};
+use unic_langid::LanguageIdentifier;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = Intl)]
+    type RelativeTimeFormat;
+
+    #[wasm_bindgen(constructor, catch, js_namespace = Intl)]
+    fn new(locale: &str, options: &JsValue) -> Result<RelativeTimeFormat, JsValue>;
+
+    #[wasm_bindgen(method)]
+    fn format(this: &RelativeTimeFormat, value: f64, unit: &str) -> String;
+}
+
+const FALLBACK: &str = "en-US";
+
+/// Every catalog, the fallback first.
+const CATALOGS: [(&str, &str); 3] = [
+    ("en-US", include_str!("../locales/en-US/main.ftl")),
+    ("de", include_str!("../locales/de/main.ftl")),
+    ("zh-CN", include_str!("../locales/zh-CN/main.ftl")),
+];
+
+fn bundle(locale: &str, source: &str) -> FluentBundle<FluentResource> {
+    let id: LanguageIdentifier = locale.parse().unwrap();
+    let mut bundle = FluentBundle::new(vec![id]);
+    // the texts end up in attributes and inputs too, where isolation marks show
+    bundle.set_use_isolating(false);
+    let resource =
+        FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, _)| resource);
+    if let Err(errors) = bundle.add_resource(resource) {
+        yew::services::ConsoleService::warn(&format!("{} catalog, {:?}", locale, errors));
+    }
+    bundle
+}
+
+/// The catalog best matching `requested`, e.g. `de` for `de-AT`.
+fn negotiate(requested: &str) -> &'static (&'static str, &'static str) {
+    let language = |l: &str| {
+        l.split(|c| c == '-' || c == '_')
+            .next()
+            .unwrap_or("")
+            .to_lowercase()
+    };
+    CATALOGS
+        .iter()
+        .find(|(l, _)| l.eq_ignore_ascii_case(requested))
+        .or_else(|| {
+            CATALOGS
+                .iter()
+                .find(|(l, _)| language(l) == language(requested))
+        })
+        .unwrap_or(&CATALOGS[0])
+}
+
+/// The browser's locale, the fallback if it has none.
+pub fn system_locale() -> String {
+    web_sys::window()
+        .and_then(|w| w.navigator().language())
+        .unwrap_or_else(|| FALLBACK.to_string())
+}
+
+pub struct I18n {
+    /// Locale for numbers and dates, as requested rather than the catalog's.
+    locale: String,
+    bundle: FluentBundle<FluentResource>,
+    /// Texts the chosen catalog lacks.
+    fallback: FluentBundle<FluentResource>,
+}
+
+impl I18n {
+    pub fn new(locale: &str) -> Self {
+        // javascript throws on malformed tags, like `de_DE`
+        let locale = match locale.replace('_', "-").parse::<LanguageIdentifier>() {
+            Ok(id) => id.to_string(),
+            Err(_) => FALLBACK.to_string(),
+        };
+        let (catalog, source) = negotiate(&locale);
+        Self {
+            locale,
+            bundle: bundle(catalog, source),
+            fallback: bundle(FALLBACK, CATALOGS[0].1),
+        }
+    }
+
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+        let mut errors = Vec::new();
+        [&self.bundle, &self.fallback].iter().find_map(|bundle| {
+            let pattern = bundle.get_message(id)?.value()?;
+            Some(
+                bundle
+                    .format_pattern(pattern, args, &mut errors)
+                    .into_owned(),
+            )
+        })
+    }
+
+    /// The text `id`, the id itself if no catalog has it.
+    pub fn tr(&self, id: &str) -> String {
+        self.format(id, None).unwrap_or_else(|| id.to_string())
+    }
+
+    /// The text `id` with its `{ $name }` placeholders filled in.
+    pub fn tr_args<'a>(&self, id: &str, args: &[(&'a str, FluentValue<'a>)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+        self.format(id, Some(&fluent_args))
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// `n` with the locale's digit grouping.
+    pub fn number(&self, n: usize) -> String {
+        js_sys::Number::from(n as f64)
+            .to_locale_string(&self.locale)
+            .into()
+    }
+
+    /// Date and time of an RFC 3339 timestamp, in the local time zone.
+    pub fn date_time(&self, time: &str) -> String {
+        js_sys::Date::new(&time.into())
+            .to_locale_string(&self.locale, &JsValue::UNDEFINED)
+            .into()
+    }
+
+    /// Hours and minutes of an RFC 3339 timestamp, in the local time zone.
+    pub fn time(&self, time: &str) -> String {
+        let options = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&options, &"hour".into(), &"2-digit".into());
+        let _ = js_sys::Reflect::set(&options, &"minute".into(), &"2-digit".into());
+        js_sys::Date::new(&time.into())
+            .to_locale_string(&self.locale, &options)
+            .into()
+    }
+
+    /// `seconds` in its two largest units, like "3 days 4 hours".
+    pub fn duration(&self, seconds: i64) -> String {
+        let minutes = seconds.max(0) / 60;
+        let units = [
+            ("duration-days", minutes / 1440),
+            ("duration-hours", minutes / 60 % 24),
+            ("duration-minutes", minutes % 60),
+        ];
+        let first = units.iter().position(|(_, n)| *n > 0).unwrap_or(2);
+        units[first..(first + 2).min(3)]
+            .iter()
+            .filter(|(_, n)| *n > 0 || first == 2)
+            .map(|(id, n)| self.tr_args(id, &[("count", (*n).into())]))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// How long ago an RFC 3339 timestamp was, like "3 days ago".
+    pub fn age(&self, time: &str) -> String {
+        let then = js_sys::Date::new(&time.into()).get_time();
+        if then.is_nan() {
+            return time.to_string();
+        }
+        let seconds = (js_sys::Date::now() - then) / 1000.0;
+        let (value, unit) = match seconds {
+            s if s < 60.0 => (s, "second"),
+            s if s < 3600.0 => (s / 60.0, "minute"),
+            s if s < 86400.0 => (s / 3600.0, "hour"),
+            s if s < 7.0 * 86400.0 => (s / 86400.0, "day"),
+            s => (s / (7.0 * 86400.0), "week"),
+        };
+        let options = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&options, &"numeric".into(), &"auto".into());
+        match RelativeTimeFormat::new(&self.locale, &options) {
+            Ok(format) => format.format(-value.floor(), unit),
+            Err(_) => time.to_string(),
+        }
+    }
+}
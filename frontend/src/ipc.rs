@@ -0,0 +1,103 @@
+//! Calls into the tauri backend.
+
+use backend_api::{Envelope, Event, Request, Response, EVENT_NAME, PROTOCOL_VERSION};
+use std::cell::Cell;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = tauri)]
+    type Tauri;
+
+    #[wasm_bindgen(method, catch, js_name = promisified)]
+    fn promisified(this: &Tauri, command: JsValue) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_name = event)]
+    type TauriEvent;
+
+    #[wasm_bindgen(method, catch)]
+    fn listen(this: &TauriEvent, event: &str, handler: &JsValue) -> Result<(), JsValue>;
+}
+
+thread_local! {
+    static NEXT_ID: Cell<u64> = Cell::new(1);
+}
+
+fn tauri() -> Result<Tauri, String> {
+    let window = web_sys::window().ok_or("no window")?;
+    let tauri = window
+        .get("__TAURI__")
+        .ok_or("tauri api is not available")?;
+    js_sys::Reflect::get(&tauri, &"tauri".into())
+        .map(|t| t.unchecked_into::<Tauri>())
+        .map_err(error_message)
+}
+
+/// Calls `on_event` with every event the backend pushes, for as long as the page lives.
+pub fn listen(on_event: impl Fn(Event) + 'static) -> Result<(), String> {
+    let window = web_sys::window().ok_or("no window")?;
+    let tauri = window
+        .get("__TAURI__")
+        .ok_or("tauri api is not available")?;
+    let event = js_sys::Reflect::get(&tauri, &"event".into())
+        .map(|e| e.unchecked_into::<TauriEvent>())
+        .map_err(error_message)?;
+    let handler = Closure::wrap(Box::new(move |message: JsValue| {
+        let payload = js_sys::Reflect::get(&message, &"payload".into()).unwrap_or_default();
+        match payload.into_serde::<Event>() {
+            Ok(e) => on_event(e),
+            Err(e) => yew::services::ConsoleService::warn(&format!("invalid backend event, {}", e)),
+        }
+    }) as Box<dyn Fn(JsValue)>);
+    event
+        .listen(EVENT_NAME, handler.as_ref())
+        .map_err(error_message)?;
+    handler.forget();
+    Ok(())
+}
+
+fn error_message(e: JsValue) -> String {
+    e.as_string().unwrap_or_else(|| format!("{:?}", e))
+}
+
+/// Sends `request` to the backend. Errors carry the backend's message.
+pub async fn send(request: Request) -> Result<Response, String> {
+    let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+    let envelope = Envelope {
+        version: PROTOCOL_VERSION,
+        id,
+        payload: request,
+    };
+    let command = JsValue::from_serde(&envelope).map_err(|e| e.to_string())?;
+    let promise = tauri()?.promisified(command).map_err(error_message)?;
+    let value = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&promise))
+        .await
+        .map_err(error_message)?;
+    let response: Envelope<Response> = value
+        .into_serde()
+        .map_err(|e| format!("invalid response, {}", e))?;
+    match response.id == id {
+        true => Ok(response.payload),
+        false => Err(format!(
+            "response {} does not answer request {}",
+            response.id, id
+        )),
+    }
+}
+
+/// Agrees on the protocol version with the backend, returns the current repo.
+pub async fn hello() -> Result<String, String> {
+    match send(Request::Hello {
+        version: PROTOCOL_VERSION,
+    })
+    .await?
+    {
+        Response::Hello { version, repo } if version == PROTOCOL_VERSION => Ok(repo),
+        Response::Hello { version, .. } => Err(format!(
+            "the backend speaks protocol version {}, this frontend needs {}",
+            version, PROTOCOL_VERSION
+        )),
+        r => Err(format!("unexpected response {:?}", r)),
+    }
+}
@@ -0,0 +1,88 @@
+//! Keyboard shortcuts. Combos are written like `Ctrl+Shift+K`, `F5` or `/` and
+//! compared case insensitively.
+
+use wasm_bindgen::JsCast;
+use web_sys::KeyboardEvent;
+
+const MODIFIERS: [&str; 4] = ["ctrl", "alt", "shift", "meta"];
+
+fn key_name(key: &str) -> String {
+    match key.to_lowercase().as_str() {
+        "space" => " ".to_string(),
+        "esc" => "escape".to_string(),
+        "up" | "down" | "left" | "right" => format!("arrow{}", key.to_lowercase()),
+        key => key.to_string(),
+    }
+}
+
+/// Splits a combo into its modifiers and the key, the `+` key included.
+fn split(combo: &str) -> (&str, &str) {
+    if combo == "+" {
+        return ("", "+");
+    }
+    match combo.strip_suffix("++") {
+        Some(modifiers) => (modifiers, "+"),
+        None => combo.rsplit_once('+').unwrap_or(("", combo)),
+    }
+}
+
+fn join(modifiers: &[bool; 4], key: String) -> String {
+    // shift is already part of symbols, `?` rather than `Shift+/`
+    let symbol = key.chars().count() == 1 && !key.chars().all(char::is_alphanumeric);
+    let mut parts: Vec<String> = MODIFIERS
+        .iter()
+        .zip(modifiers)
+        .filter(|(m, &on)| on && !(symbol && **m == "shift"))
+        .map(|(m, _)| m.to_string())
+        .collect();
+    parts.push(key);
+    parts.join("+")
+}
+
+/// The combo of a key event.
+pub fn combo(event: &KeyboardEvent) -> String {
+    let modifiers = [
+        event.ctrl_key(),
+        event.alt_key(),
+        event.shift_key(),
+        event.meta_key(),
+    ];
+    join(&modifiers, event.key().to_lowercase())
+}
+
+/// A combo from the settings in the form `combo` returns, empty if unbound.
+pub fn normalize(keys: &str) -> String {
+    let keys = keys.trim();
+    if keys.is_empty() {
+        return String::new();
+    }
+    let (modifiers, key) = split(keys);
+    let mut on = [false; 4];
+    for modifier in modifiers.split('+').map(|m| m.trim().to_lowercase()) {
+        let modifier = match modifier.as_str() {
+            "control" => "ctrl",
+            "cmd" | "win" => "meta",
+            m => m,
+        };
+        if let Some(i) = MODIFIERS.iter().position(|m| *m == modifier) {
+            on[i] = true;
+        }
+    }
+    join(&on, key_name(key).to_lowercase())
+}
+
+/// Whether the combo would type into a text field, so it is left to the field.
+pub fn is_typing(combo: &str) -> bool {
+    let (modifiers, key) = split(combo);
+    key.chars().count() == 1 && modifiers.split('+').all(|m| m.is_empty() || m == "shift")
+}
+
+/// Whether the event comes from a text field.
+pub fn in_text_field(event: &KeyboardEvent) -> bool {
+    event
+        .target()
+        .and_then(|t| t.dyn_into::<web_sys::Element>().ok())
+        .map_or(false, |e| {
+            matches!(e.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        })
+}
@@ -1,32 +1,159 @@
 #![recursion_limit = "1024"]
 
+mod i18n;
+mod ipc;
+mod keys;
+mod paged;
+
 use backend_api as api;
-use backend_api::Response;
-use std::collections::HashMap;
+use backend_api::{Request, Response};
+use i18n::I18n;
+use paged::{PagedList, PAGE_SIZE};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::time::{Duration, SystemTime};
-use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use yew::prelude::*;
+use yew::services::interval::{IntervalService, IntervalTask};
+use yew::services::keyboard::{KeyListenerHandle, KeyboardService};
+use yew::services::timeout::{TimeoutService, TimeoutTask};
 use yew::services::ConsoleService;
 use yewtil::future::LinkFuture;
 
 #[derive(Clone, Debug)]
 enum Msg {
     AddOne,
-    Echo { message: String },
+    /// Connects to the backend and loads the current repo.
+    Hello,
     PickRepo,
-    RepoPicked { repo: String },
+    RepoPicked {
+        repo: String,
+    },
     FilterChanged(String),
+    ToggleAllFiles,
     GetLockedFiles,
-    LockedFilesReceived(Vec<String>),
-    FilteredFilesReceived(Vec<String>),
+    /// Refreshes the locks and looks for pushed ones.
+    ForceRefresh,
+    FetchPages,
+    Scrolled {
+        top: i32,
+        height: i32,
+    },
+    LockedFilesReceived {
+        offset: usize,
+        locked_files: Vec<api::LockEntry>,
+        total: usize,
+        refreshed: bool,
+        offline: Option<api::Offline>,
+    },
+    FilteredFilesReceived {
+        filter: String,
+        all_files: bool,
+        offset: usize,
+        filtered_files: Vec<api::SearchResult>,
+        total: usize,
+    },
+    FilterFailed {
+        filter: String,
+        all_files: bool,
+        error: String,
+    },
     LockFile(String),
-    UnlockFile(String),
-    FileLocked(Option<api::LockEntry>),
+    UnlockFile(api::LockEntry),
+    FileLocked(api::LockEntry),
     FileUnlocked(String),
     UnlockAll,
+    UnlockPreviewReceived(Vec<api::UnlockPreview>),
+    ConfirmUnlockAll,
+    CancelUnlockAll,
+    CheckPushedLocks,
+    PushedLocksReceived {
+        pending: Vec<api::LockEntry>,
+        released: api::BatchResult,
+    },
+    ConfirmRelease,
+    DismissRelease,
+    GetQuickAccess,
+    QuickAccessReceived {
+        pinned: Vec<api::SearchResult>,
+        recent: Vec<api::SearchResult>,
+        watched: Vec<api::SearchResult>,
+        queued: Vec<api::SearchResult>,
+        pending: Vec<api::PendingOperation>,
+    },
+    PinFile {
+        path: String,
+        pinned: bool,
+    },
+    WatchFile {
+        path: String,
+        watched: bool,
+    },
+    QueueLock {
+        path: String,
+        queued: bool,
+    },
+    CancelPending(String),
+    GetInbox,
+    InboxReceived(Vec<api::WatchEvent>),
+    ToggleInbox,
+    ClearInbox,
+    ToggleTreeView,
+    GetTree(String),
+    TreeReceived {
+        dir: String,
+        nodes: Vec<api::TreeNode>,
+    },
+    ToggleDir(String),
+    LockFolder(String),
+    UnlockFolder(String),
+    BatchDone(api::BatchResult),
+    DismissReport,
+    /// Shows `String` in the notification area.
+    Notify(String),
+    /// Notifies that the action with the text id `action` failed, `path` fills in
+    /// its `{ $path }`. `result` is the error, or the unexpected response printed.
+    Failed {
+        action: &'static str,
+        path: Option<String>,
+        result: Result<String, String>,
+    },
+    DismissNotification(usize),
+    /// Pushed by the backend on its own.
+    BackendEvent(api::Event),
+    /// Reloads the locks once a burst of lock events is over.
+    ReloadLocks,
+    KeyDown(KeyboardEvent),
+    RunCommand(api::Command),
+    GetKeyBindings,
+    KeyBindingsReceived(Vec<(api::Command, String)>),
+    /// Locale from the settings, empty for the system's.
+    LocaleReceived(String),
+    ToggleStats,
+    GetLockStats,
+    LockStatsReceived(api::LockStats),
+    StaleDaysChanged(String),
+    OpenExport,
+    ExportLocks(api::ExportFormat),
+    LocksExported {
+        path: Option<String>,
+        count: usize,
+    },
+    CloseExport,
+    PromptInput(String),
+    /// Answers the shown credential prompt with the typed answer, `false` cancels it.
+    AnswerPrompt(bool),
+    /// The prompt was answered, cancelled or timed out, from here or elsewhere.
+    PromptClosed(u32),
+    PaletteFilterChanged(String),
+    ClosePalette,
 }
 
+/// Height of a table row in px, has to match `.virtual-table td` in index.html.
+const ROW_HEIGHT: i32 = 48;
+/// Rows rendered above and below the visible ones.
+const OVERSCAN: usize = 10;
+
 enum ListType {
     LockedFiles,
     SearchResult,
@@ -39,127 +166,430 @@ struct Model {
     value: i64,
     repo: String,
     filter: String,
-    locked_files: HashMap<String, (String, u32)>,
-    filtered_files: Vec<String>,
+    /// Search every tracked file instead of lfs objects only.
+    all_files: bool,
+    locked_files: PagedList<api::LockEntry>,
+    filtered_files: PagedList<api::SearchResult>,
+    /// Why the backend rejected the current filter, e.g. a query syntax error.
+    filter_error: Option<String>,
+    /// Quick access panel shown while the filter is empty.
+    pinned: Vec<api::SearchResult>,
+    recent: Vec<api::SearchResult>,
+    watched: Vec<api::SearchResult>,
+    /// Files waiting to be locked for us once free.
+    queued: Vec<api::SearchResult>,
+    /// Locks and unlocks asked for while offline, waiting for the server.
+    pending: Vec<api::PendingOperation>,
+    /// Lock changes of watched files, oldest first.
+    inbox: Vec<api::WatchEvent>,
+    inbox_open: bool,
+    /// Inbox events the user has already looked at.
+    inbox_seen: usize,
+    _inbox_poll: IntervalTask,
+    /// Show the directory tree instead of the lock list while the filter is empty.
+    tree_view: bool,
+    /// Loaded tree levels by folder path, the root is "".
+    tree: HashMap<String, Vec<api::TreeNode>>,
+    expanded: HashSet<String>,
+    /// Report of the last folder lock or unlock.
+    batch_result: Option<api::BatchResult>,
+    /// Progress of the running folder lock or unlock, as (job, done, total).
+    job: Option<(String, usize, usize)>,
+    /// Pending reload after lock events, replaced by each new one.
+    _reload: Option<TimeoutTask>,
+    /// Our locks awaiting confirmation of "Unlock All Mine".
+    unlock_preview: Option<Vec<api::UnlockPreview>>,
+    /// Our pushed locks awaiting confirmation to unlock.
+    pushed_locks: Vec<api::LockEntry>,
+    /// Ids of pushed locks the user chose to keep, not asked about again.
+    kept_locks: HashSet<String>,
+    /// Show the statistics dashboard instead of the lists.
+    stats_open: bool,
+    stats: Option<api::LockStats>,
+    /// Stale lock age picked on the dashboard, the settings' until changed.
+    stale_days: Option<u32>,
+    export_open: bool,
+    /// Where the last export went, shown in the export dialog.
+    exported: Option<String>,
+    /// Git and ssh prompts as `(id, prompt, secret)`, the first one is shown.
+    prompts: Vec<(u32, String, bool)>,
+    prompt_answer: String,
+    prompt_ref: NodeRef,
+    /// Errors and other messages for the user, oldest first.
+    notifications: Vec<String>,
+    /// Set while the lfs server can't be reached and the last known locks are listed.
+    offline: Option<api::Offline>,
+    /// Keys of every command as written in the settings.
+    key_bindings: Vec<(api::Command, String)>,
+    _key_listener: Option<KeyListenerHandle>,
+    /// Row of the current list the keyboard acts on.
+    selected: Option<usize>,
+    palette_open: bool,
+    palette_filter: String,
+    palette_selected: usize,
+    /// Focus the palette input once it is rendered.
+    focus_palette: bool,
+    search_ref: NodeRef,
+    palette_ref: NodeRef,
+    i18n: I18n,
     list_type: ListType,
     update_time: f64,
+    table_ref: NodeRef,
+    scroll_top: i32,
+    viewport_height: i32,
 }
 
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_name = tauri)]
-    pub type Tauri;
+/// Message for a failed action, `action` being the id of its text, or for a response
+/// of the wrong kind.
+fn failure(action: &'static str, result: Result<Response, String>) -> Msg {
+    failure_on(action, None, result)
+}
 
-    #[wasm_bindgen(method, catch, js_name = promisified)]
-    pub fn promisified(this: &Tauri, command: JsValue) -> Result<JsValue, JsValue>;
+/// Like `failure`, for an action whose text names `path`.
+fn failure_on(action: &'static str, path: Option<String>, result: Result<Response, String>) -> Msg {
+    Msg::Failed {
+        action,
+        path,
+        result: result.map(|r| format!("{:?}", r)),
+    }
+}
 
-    #[wasm_bindgen(method, js_name = invoke)]
-    fn invoke(this: &Tauri, command: JsValue);
+/// Renders `path` with the chars at `indices` emphasized.
+fn highlighted(path: &str, indices: &[usize]) -> Html {
+    let mut runs: Vec<(String, bool)> = Vec::new();
+    for (i, c) in path.chars().enumerate() {
+        let matched = indices.contains(&i);
+        match runs.last_mut() {
+            Some((run, m)) if *m == matched => run.push(c),
+            _ => runs.push((c.to_string(), matched)),
+        }
+    }
+    html! {
+        <>
+            { for runs.into_iter().map(|(run, matched)| match matched {
+                true => html! { <span class="match">{ run }</span> },
+                false => html! { { run } },
+            }) }
+        </>
+    }
 }
 
-#[wasm_bindgen]
-pub fn get_tauri() -> Result<Tauri, JsValue> {
-    let window = web_sys::window().unwrap();
-    let tauri = window.get("__TAURI__").unwrap();
-    let tauri: Tauri = js_sys::Reflect::get(&tauri, &"tauri".into())
-        .unwrap()
-        .unchecked_into::<Tauri>();
+impl Model {
+    /// Rows of a list with `total` entries that are in or near the viewport.
+    fn visible_rows(&self, total: usize) -> Range<usize> {
+        let first = (self.scroll_top / ROW_HEIGHT) as usize;
+        let count = (self.viewport_height / ROW_HEIGHT) as usize + 1;
+        first.saturating_sub(OVERSCAN).min(total)..(first + count + OVERSCAN).min(total)
+    }
 
-    Ok(tauri)
-}
+    fn reset_scroll(&mut self) {
+        self.scroll_top = 0;
+        self.selected = None;
+        if let Some(table) = self.table_ref.cast::<web_sys::Element>() {
+            table.set_scroll_top(0);
+        }
+    }
 
-#[wasm_bindgen]
-pub async fn pick_repo() -> Result<String, JsValue> {
-    let tauri = get_tauri().unwrap();
-    let value: JsValue = tauri
-        .promisified(JsValue::from_serde(&api::Request::PickRepo).unwrap())
-        .unwrap();
-    let future = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&value));
-    let response: api::Response = future.await?.into_serde().unwrap();
-    match response {
-        Response::PickRepo { path } => Ok(path),
-        _ => Err(JsValue::from_str("failed to get pick repo response")),
+    /// Sends `request` to the backend, `on_response` turns the outcome into a message.
+    fn send<F>(&self, request: Request, on_response: F)
+    where
+        F: FnOnce(Result<Response, String>) -> Msg + 'static,
+    {
+        self.link
+            .send_future(async move { on_response(ipc::send(request).await) });
     }
-}
 
-#[wasm_bindgen]
-pub async fn echo() -> Result<String, JsValue> {
-    let tauri = get_tauri().unwrap();
-    let value: JsValue = tauri
-        .promisified(
-            JsValue::from_serde(&api::Request::Echo {
-                message: "useless".into(),
-            })
-            .unwrap(),
-        )
-        .unwrap();
-    let future = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&value));
-    let response: api::Response = future.await?.into_serde().unwrap();
-    match response {
-        Response::PickRepo { path } => Ok(path),
-        _ => Err(JsValue::from_str("failed to get pick repo response")),
+    fn fetch_locked_page(&self, offset: usize, cached: bool) {
+        let request = Request::GetLockedFiles {
+            offset,
+            limit: Some(PAGE_SIZE),
+            cached,
+        };
+        self.send(request, move |r| match r {
+            Ok(Response::GetLockedFiles {
+                locked_files,
+                total,
+                offline,
+            }) => Msg::LockedFilesReceived {
+                offset,
+                locked_files,
+                total,
+                refreshed: !cached,
+                offline,
+            },
+            r => failure("action-get-locked-files", r),
+        });
+    }
+
+    fn fetch_filtered_page(&self, offset: usize) {
+        let filter = self.filter.clone();
+        let all_files = self.all_files;
+        let request = Request::GetFilteredFiles {
+            filter: filter.clone(),
+            offset,
+            limit: Some(PAGE_SIZE),
+            all_files,
+        };
+        self.send(request, move |r| match r {
+            Ok(Response::GetFilteredFiles {
+                filtered_files,
+                total,
+            }) => Msg::FilteredFilesReceived {
+                filter,
+                all_files,
+                offset,
+                filtered_files,
+                total,
+            },
+            // shown next to the search box, most likely a query syntax error
+            Err(error) => Msg::FilterFailed {
+                filter,
+                all_files,
+                error,
+            },
+            r => failure("action-search", r),
+        });
     }
-}
 
-#[wasm_bindgen]
-pub async fn get_locked_files() -> Result<js_sys::Array, JsValue> {
-    let tauri = get_tauri().unwrap();
-    let value: JsValue = tauri
-        .promisified(JsValue::from_serde(&api::Request::GetLockedFiles).unwrap())
-        .unwrap();
-    let future = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&value));
-    let response: api::Response = future.await?.into_serde().unwrap();
-    match response {
-        Response::GetLockedFiles { locked_files } => {
-            Ok(locked_files.into_iter().map(JsValue::from).collect())
+    fn batch_folder(&self, dir: String, lock: bool) {
+        let request = match lock {
+            true => Request::LockFolder { dir },
+            false => Request::UnlockFolder { dir },
+        };
+        self.send(request, |r| match r {
+            Ok(Response::LockFolder { result }) | Ok(Response::UnlockFolder { result }) => {
+                Msg::BatchDone(result)
+            }
+            r => failure("action-change-folder-locks", r),
+        });
+    }
+
+    /// Rows of the loaded tree below `dir`, expanded folders followed by their content.
+    fn tree_rows(&self, dir: &str, depth: usize) -> Html {
+        let nodes = match self.tree.get(dir) {
+            Some(nodes) => nodes,
+            None => return html! {},
+        };
+        let indent = format!("padding-left: {}em", 1.0 + depth as f32 * 1.5);
+        html! {
+            { for nodes.iter().map(|node| {
+                let path = node.path.clone();
+                match node.is_dir {
+                    true => {
+                        let expanded = self.expanded.contains(&node.path);
+                        let owners: Vec<String> = node
+                            .owners
+                            .iter()
+                            .map(|(owner, count)| format!("{} {}", owner, count))
+                            .collect();
+                        let (lock_dir, unlock_dir) = (path.clone(), path.clone());
+                        html! {
+                            <>
+                                <tr class="tree-folder">
+                                    <td style=indent.clone() onclick=self.link.callback(move |_| Msg::ToggleDir(path.clone()))>
+                                        { if expanded { "\u{25be} " } else { "\u{25b8} " } }
+                                        { &node.name }
+                                        <span class="tag">{ self.i18n.tr_args("folder-locked", &[("locked", node.locked.into()), ("files", node.files.into())]) }</span>
+                                    </td>
+                                    <td>{ owners.join(", ") }</td>
+                                    <td class={"center"}>
+                                        <button class="pure-button pure-button-primary" onclick=self.link.callback(move |_| Msg::LockFolder(lock_dir.clone()))>{ self.i18n.tr("lock-all") }</button>
+                                        <button class="pure-button button-success" onclick=self.link.callback(move |_| Msg::UnlockFolder(unlock_dir.clone()))>{ self.i18n.tr("unlock-mine") }</button>
+                                    </td>
+                                </tr>
+                                { if expanded { self.tree_rows(&node.path, depth + 1) } else { html! {} } }
+                            </>
+                        }
+                    }
+                    false => {
+                        let (button_text, button_type, event) = match &node.lock {
+                            Some(l) => (self.i18n.tr("unlock"), "pure-button button-success", Msg::UnlockFile(l.clone())),
+                            None => (self.i18n.tr("lock"), "pure-button pure-button-primary", Msg::LockFile(path)),
+                        };
+                        html! {
+                            <tr>
+                                <td style=indent.clone()>{ &node.name }</td>
+                                <td>{ node.lock.as_ref().map(|l| self.locked_by(l)).unwrap_or_default() }</td>
+                                <td class={"center"}>
+                                    <button class=button_type onclick=self.link.callback(move |_| event.clone())>{ button_text }</button>
+                                </td>
+                            </tr>
+                        }
+                    }
+                }
+            }) }
         }
-        _ => Err(JsValue::from_str("failed to get locked files response")),
     }
-}
 
-#[wasm_bindgen]
-pub async fn get_filtered_files(filter: String) -> Result<js_sys::Array, JsValue> {
-    let tauri = get_tauri().unwrap();
-    let value: JsValue = tauri
-        .promisified(JsValue::from_serde(&api::Request::GetFilteredFiles { filter }).unwrap())
-        .unwrap();
-    let future = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&value));
-    let response: api::Response = future.await?.into_serde().unwrap();
-    match response {
-        Response::GetFilteredFiles { filtered_files } => {
-            Ok(filtered_files.into_iter().map(JsValue::from).collect())
+    /// Rows of the list on screen, the tree has no keyboard selection.
+    fn selectable_rows(&self) -> usize {
+        match (&self.list_type, self.tree_view) {
+            (ListType::LockedFiles, true) => 0,
+            (ListType::LockedFiles, false) => self.locked_files.total,
+            (ListType::SearchResult, _) => self.filtered_files.total,
         }
-        _ => Err(JsValue::from_str("failed to get filtered files response")),
     }
-}
 
-#[wasm_bindgen]
-pub async fn unlock_file(id: u32) -> Result<u32, JsValue> {
-    let tauri = get_tauri().unwrap();
-    let value: JsValue = tauri
-        .promisified(JsValue::from_serde(&api::Request::UnlockFile { id }).unwrap())
-        .unwrap();
-    let future = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&value));
-    ConsoleService::log("unlocking in async");
-    let response: api::Response = future.await?.into_serde().unwrap();
-    match response {
-        Response::UnlockFile { id } => Ok(id),
-        _ => Err(JsValue::from_str("failed to get unlock file response")),
+    /// Moves the selection by `step` rows and scrolls it into view.
+    fn move_selection(&mut self, step: i32) {
+        let rows = self.selectable_rows();
+        if rows == 0 {
+            return;
+        }
+        let selected = match self.selected {
+            None => 0,
+            Some(i) => (i as i32 + step).max(0).min(rows as i32 - 1) as usize,
+        };
+        self.selected = Some(selected);
+        // the sticky header is about a row high
+        let top = selected as i32 * ROW_HEIGHT;
+        let scroll_top = if top < self.scroll_top {
+            top
+        } else if top + 2 * ROW_HEIGHT > self.scroll_top + self.viewport_height {
+            top + 2 * ROW_HEIGHT - self.viewport_height
+        } else {
+            return;
+        };
+        if let Some(table) = self.table_ref.cast::<web_sys::Element>() {
+            table.set_scroll_top(scroll_top);
+        }
+    }
+
+    /// Lock or unlock of the selected row.
+    fn selected_action(&self) -> Option<Msg> {
+        let i = self.selected?;
+        match self.list_type {
+            ListType::LockedFiles => self.locked_files.get(i).map(|l| Msg::UnlockFile(l.clone())),
+            ListType::SearchResult => self.filtered_files.get(i).map(|r| match &r.lock {
+                Some(l) => Msg::UnlockFile(l.clone()),
+                None => Msg::LockFile(r.path.clone()),
+            }),
+        }
     }
-}
 
-#[wasm_bindgen]
-pub async fn lock_file(path: String) -> Result<String, JsValue> {
-    let tauri = get_tauri().unwrap();
-    let value: JsValue = tauri
-        .promisified(JsValue::from_serde(&api::Request::LockFile { path }).unwrap())
-        .unwrap();
-    let future = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&value));
-    ConsoleService::log("locking in async");
-    let response: api::Response = future.await?.into_serde().unwrap();
-    match response {
-        Response::LockFile { lock_entry } => Ok(serde_json::to_string(&lock_entry).unwrap()),
-        _ => Err(JsValue::from_str("failed to get lock file response")),
+    /// Commands matching the palette filter, the palette itself left out.
+    fn palette_commands(&self) -> Vec<&(api::Command, String)> {
+        let filter = self.palette_filter.to_lowercase();
+        self.key_bindings
+            .iter()
+            .filter(|(c, _)| *c != api::Command::OpenPalette)
+            .filter(|(c, _)| self.command_title(*c).to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    fn command_title(&self, command: api::Command) -> String {
+        match serde_json::to_value(command) {
+            Ok(serde_json::Value::String(id)) => self.i18n.tr(&format!("command-{}", id)),
+            _ => command.title().to_string(),
+        }
+    }
+
+    /// Owner of `lock` and how long ago it was taken, the date on hover.
+    fn locked_by(&self, lock: &api::LockEntry) -> Html {
+        html! {
+            <>
+                { &lock.owner.name }
+                <span class="lock-age" title=self.i18n.tr_args("locked-since", &[("date", self.i18n.date_time(&lock.locked_at).into())])>
+                    { self.i18n.age(&lock.locked_at) }
+                </span>
+            </>
+        }
+    }
+
+    /// The lock statistics with a bar chart per owner and per directory.
+    fn dashboard(&self) -> Html {
+        let stats = match &self.stats {
+            Some(stats) => stats,
+            None => return html! { <div class="dashboard">{ "..." }</div> },
+        };
+        let chart = |title: &str, counts: &[(String, usize)]| {
+            let max = counts.iter().map(|(_, c)| *c).max().unwrap_or(1);
+            html! {
+                <div class="chart">
+                    <h4>{ self.i18n.tr(title) }</h4>
+                    { for counts.iter().map(|(key, count)| html! {
+                        <div class="bar-row">
+                            <span class="bar-label" title=key.clone()>{ if key.is_empty() { "/" } else { key } }</span>
+                            <div class="bar" style=format!("width: {}%", count * 100 / max)>{ self.i18n.number(*count) }</div>
+                        </div>
+                    }) }
+                </div>
+            }
+        };
+        let ages = match (stats.median_age, stats.longest_age, &stats.oldest) {
+            (Some(median), Some(longest), Some(oldest)) => html! {
+                <>
+                    <p>{ self.i18n.tr_args("stats-median", &[("age", self.i18n.duration(median).into())]) }</p>
+                    <p>{ self.i18n.tr_args("stats-longest", &[
+                        ("age", self.i18n.duration(longest).into()),
+                        ("owner", oldest.owner.name.as_str().into()),
+                        ("path", oldest.path.as_str().into()),
+                    ]) }</p>
+                </>
+            },
+            _ => html! {},
+        };
+        html! {
+            <div class="dashboard">
+                <h3>{ self.i18n.tr_args("stats-total", &[("count", stats.total.into())]) }</h3>
+                { ages }
+                <div class="charts">
+                    { chart("stats-per-owner", &stats.per_owner) }
+                    { chart("stats-per-directory", &stats.per_directory) }
+                </div>
+                <h4>
+                    { self.i18n.tr_args("stats-stale", &[("count", stats.stale.len().into())]) }
+                    <input type="number" min="0" class="stale-days" value=stats.stale_days.to_string() oninput=self.link.callback(|e: InputData| Msg::StaleDaysChanged(e.value))/>
+                    { self.i18n.tr("stats-days") }
+                </h4>
+                <table class="pure-table">
+                    <tbody>
+                        { for stats.stale.iter().map(|l| html! {
+                            <tr>
+                                <td>{ &l.path }</td>
+                                <td>{ self.locked_by(l) }</td>
+                            </tr>
+                        }) }
+                    </tbody>
+                </table>
+            </div>
+        }
+    }
+
+    /// What happened in `event`, in the interface's language.
+    fn describe(&self, event: &api::WatchEvent) -> String {
+        let id = match (event.automatic, event.locked) {
+            (true, true) => "inbox-acquired",
+            (true, false) => "inbox-released",
+            (false, true) => "inbox-locked",
+            (false, false) => "inbox-unlocked",
+        };
+        self.i18n.tr_args(
+            id,
+            &[
+                ("owner", event.owner.as_str().into()),
+                ("path", event.path.as_str().into()),
+            ],
+        )
+    }
+
+    /// Renders the rows of `list` around the viewport, with spacers standing in
+    /// for the rest so the scrollbar matches the full list.
+    fn virtual_rows<T>(&self, list: &PagedList<T>, row: impl Fn(&T, bool) -> Html) -> Html {
+        let rows = self.visible_rows(list.total);
+        let spacer = |count: usize| {
+            html! { <tr style=format!("height: {}px", count as i32 * ROW_HEIGHT)></tr> }
+        };
+        html! {
+            <>
+                { spacer(rows.start) }
+                { for rows.clone().map(|i| match list.get(i) {
+                    Some(item) => row(item, self.selected == Some(i)),
+                    None => html! { <tr><td colspan="3">{ "..." }</td></tr> },
+                }) }
+                { spacer(list.total - rows.end) }
+            </>
+        }
     }
 }
 
@@ -176,20 +606,71 @@ impl Component for Model {
     type Properties = ();
 
     fn create(_props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        link.send_future(async {
-            Msg::Echo {
-                message: "what".into(),
-            }
-        });
+        link.send_future(async { Msg::Hello });
+        let events = link.clone();
+        if let Err(e) = ipc::listen(move |e| events.send_message(Msg::BackendEvent(e))) {
+            ConsoleService::warn(&format!("not listening to backend events, {}", e));
+        }
+        let inbox_poll =
+            IntervalService::spawn(Duration::from_secs(15), link.callback(|_| Msg::GetInbox));
+        let key_listener = web_sys::window()
+            .and_then(|w| w.document())
+            .map(|d| KeyboardService::register_key_down(&d, link.callback(Msg::KeyDown)));
         Self {
             link,
             value: 0,
             repo: String::new(),
             filter: String::new(),
-            locked_files: HashMap::new(),
-            filtered_files: Vec::new(),
+            all_files: false,
+            locked_files: PagedList::new(),
+            filtered_files: PagedList::new(),
+            filter_error: None,
+            pinned: Vec::new(),
+            recent: Vec::new(),
+            watched: Vec::new(),
+            queued: Vec::new(),
+            pending: Vec::new(),
+            inbox: Vec::new(),
+            inbox_open: false,
+            inbox_seen: 0,
+            _inbox_poll: inbox_poll,
+            tree_view: false,
+            tree: HashMap::new(),
+            expanded: HashSet::new(),
+            batch_result: None,
+            job: None,
+            _reload: None,
+            unlock_preview: None,
+            pushed_locks: Vec::new(),
+            kept_locks: HashSet::new(),
+            stats_open: false,
+            stats: None,
+            stale_days: None,
+            export_open: false,
+            exported: None,
+            prompts: Vec::new(),
+            prompt_answer: String::new(),
+            prompt_ref: NodeRef::default(),
+            notifications: Vec::new(),
+            offline: None,
+            key_bindings: api::Command::ALL
+                .iter()
+                .map(|c| (*c, c.default_keys().to_string()))
+                .collect(),
+            _key_listener: key_listener,
+            selected: None,
+            palette_open: false,
+            palette_filter: String::new(),
+            palette_selected: 0,
+            focus_palette: false,
+            search_ref: NodeRef::default(),
+            palette_ref: NodeRef::default(),
+            i18n: I18n::new(&i18n::system_locale()),
             list_type: ListType::LockedFiles,
             update_time: 0.0,
+            table_ref: NodeRef::default(),
+            scroll_top: 0,
+            viewport_height: 600,
         }
     }
 
@@ -202,27 +683,26 @@ impl Component for Model {
                 true
             }
             Msg::PickRepo => {
-                self.link.send_future(async {
-                    match pick_repo().await {
-                        Ok(repo) => Msg::RepoPicked { repo },
-                        Err(_) => Msg::RepoPicked {
-                            repo: String::new(),
-                        },
-                    }
+                self.send(Request::PickRepo, |r| match r {
+                    Ok(Response::PickRepo { path }) => Msg::RepoPicked { repo: path },
+                    r => failure("action-pick-repo", r),
                 });
                 true
             }
-            Msg::Echo { message } => {
+            Msg::Hello => {
                 ConsoleService::log("initialize");
+                self.link.send_message(Msg::GetKeyBindings);
+                self.send(Request::GetLocale, |r| match r {
+                    Ok(Response::GetLocale { locale }) => Msg::LocaleReceived(locale),
+                    r => failure("action-get-locale", r),
+                });
                 self.link.send_future(async {
-                    match echo().await {
+                    match ipc::hello().await {
                         Ok(repo) => Msg::RepoPicked { repo },
-                        Err(_) => Msg::RepoPicked {
-                            repo: String::new(),
-                        },
+                        Err(error) => failure("action-connect", Err(error)),
                     }
                 });
-                true
+                false
             }
             Msg::RepoPicked { repo } => {
                 if !repo.is_empty() {
@@ -230,30 +710,36 @@ impl Component for Model {
                     self.list_type = ListType::LockedFiles;
                     self.filtered_files.clear();
                     self.filter.clear();
+                    self.reset_scroll();
                     self.link.send_message(Msg::GetLockedFiles);
+                    self.link.send_message(Msg::GetQuickAccess);
+                    self.link.send_message(Msg::CheckPushedLocks);
                     true
                 } else {
                     false
                 }
             }
             Msg::FilterChanged(filter) => {
-                match filter.is_empty() {
+                self.filter = filter;
+                self.filter_error = None;
+                self.filtered_files.clear();
+                self.reset_scroll();
+                match self.filter.is_empty() {
                     true => self.list_type = ListType::LockedFiles,
                     false => {
                         ConsoleService::log(&"filterstart");
-                        let filter = filter.clone();
                         self.list_type = ListType::SearchResult;
-                        self.link.send_future(async {
-                            match get_filtered_files(filter).await {
-                                Ok(arr) => Msg::FilteredFilesReceived(
-                                    arr.iter().map(|v| v.as_string().unwrap()).collect(),
-                                ),
-                                Err(_) => Msg::FilteredFilesReceived(Vec::new()),
-                            }
-                        })
+                        for offset in self.filtered_files.missing(0..PAGE_SIZE) {
+                            self.fetch_filtered_page(offset);
+                        }
                     }
                 };
-                self.filter = filter;
+                true
+            }
+            Msg::ToggleAllFiles => {
+                self.all_files = !self.all_files;
+                self.link
+                    .send_message(Msg::FilterChanged(self.filter.clone()));
                 true
             }
             Msg::GetLockedFiles => {
@@ -261,16 +747,7 @@ impl Component for Model {
                 ConsoleService::log("updating");
                 match self.repo.is_empty() {
                     true => ConsoleService::log("did not select git repo yet"),
-                    false => {
-                        self.link.send_future(async {
-                            match get_locked_files().await {
-                                Ok(arr) => Msg::LockedFilesReceived(
-                                    arr.iter().map(|v| v.as_string().unwrap()).collect(),
-                                ),
-                                Err(_) => Msg::LockedFilesReceived(Vec::new()),
-                            }
-                        });
-                    }
+                    false => self.fetch_locked_page(0, false),
                 }
                 //     self.update_time = js_sys::Date::now() / 1000.0;
                 // } else {
@@ -279,83 +756,632 @@ impl Component for Model {
 
                 false
             }
-            Msg::LockedFilesReceived(v) => {
-                ConsoleService::log(&format!("updated {:?}", &v));
-                self.locked_files.clear();
-                for entry in v {
-                    let entry: Vec<String> =
-                        entry.split_whitespace().map(|s| s.to_string()).collect();
-
-                    self.locked_files.insert(
-                        entry.get(0).unwrap().clone(),
-                        (
-                            entry[1..entry.len() - 1].join(" "),
-                            entry[entry.len() - 1][3..].parse().unwrap(),
-                        ),
-                    );
+            Msg::ForceRefresh => {
+                self.link.send_message(Msg::GetLockedFiles);
+                self.link.send_message(Msg::CheckPushedLocks);
+                false
+            }
+            Msg::FetchPages => {
+                match self.list_type {
+                    ListType::LockedFiles => {
+                        let rows = self.visible_rows(self.locked_files.total);
+                        for offset in self.locked_files.missing(rows) {
+                            self.fetch_locked_page(offset, true);
+                        }
+                    }
+                    ListType::SearchResult => {
+                        let rows = self.visible_rows(self.filtered_files.total);
+                        for offset in self.filtered_files.missing(rows) {
+                            self.fetch_filtered_page(offset);
+                        }
+                    }
+                }
+                false
+            }
+            Msg::Scrolled { top, height } => {
+                self.scroll_top = top;
+                self.viewport_height = height;
+                self.link.send_message(Msg::FetchPages);
+                true
+            }
+            Msg::LockedFilesReceived {
+                offset,
+                locked_files,
+                total,
+                refreshed,
+                offline,
+            } => {
+                ConsoleService::log(&format!("updated {} of {}", locked_files.len(), total));
+                self.offline = offline;
+                if refreshed {
+                    // the backend has a new lock list, everything cached is stale
+                    self.locked_files.clear();
+                    self.link.send_message(Msg::GetQuickAccess);
+                    for dir in self.tree.keys() {
+                        self.link.send_message(Msg::GetTree(dir.clone()));
+                    }
+                    if let ListType::SearchResult = self.list_type {
+                        self.filtered_files.clear();
+                    }
+                }
+                self.locked_files.insert(offset, locked_files, total);
+                self.link.send_message(Msg::FetchPages);
+                true
+            }
+            Msg::FilteredFilesReceived {
+                filter,
+                all_files,
+                offset,
+                filtered_files,
+                total,
+            } => {
+                if filter != self.filter || all_files != self.all_files {
+                    return false;
+                }
+                if let Some(r) = filtered_files.first() {
+                    ConsoleService::log(&r.path);
                 }
+                self.filtered_files.insert(offset, filtered_files, total);
+                self.link.send_message(Msg::FetchPages);
                 true
             }
-            Msg::FilteredFilesReceived(v) => {
-                self.filtered_files = v;
-                if !self.filtered_files.is_empty() {
-                    ConsoleService::log(&self.filtered_files[0]);
+            Msg::FilterFailed {
+                filter,
+                all_files,
+                error,
+            } => {
+                if filter != self.filter || all_files != self.all_files {
+                    return false;
                 }
+                self.filter_error = Some(error);
                 true
             }
             Msg::LockFile(v) => {
                 ConsoleService::log("locking");
-                if !self.locked_files.contains_key(&v.clone()) {
-                    self.link.send_future(async {
-                        match lock_file(v).await {
-                            Ok(s) => {
-                                let lock_entry =
-                                    serde_json::from_str::<api::LockEntry>(s.as_str()).unwrap();
-                                Msg::FileLocked(Some(lock_entry))
-                            }
-                            Err(_) => Msg::FileLocked(None),
-                        }
-                    });
+                let path = v.clone();
+                self.send(Request::LockFile { path: v }, move |r| match r {
+                    Ok(Response::LockFile { lock_entry }) => Msg::FileLocked(lock_entry),
+                    Ok(Response::Pending { .. }) => Msg::GetQuickAccess,
+                    r => failure_on("action-lock", Some(path), r),
+                });
+                false
+            }
+            Msg::UnlockFile(entry) => {
+                let id = match entry.id.parse() {
+                    Ok(id) => id,
+                    Err(_) => {
+                        let error = self
+                            .i18n
+                            .tr_args("invalid-lock-id", &[("id", entry.id.as_str().into())]);
+                        return self.update(failure_on(
+                            "action-unlock",
+                            Some(entry.path),
+                            Err(error),
+                        ));
+                    }
+                };
+                self.send(Request::UnlockFile { id }, move |r| match r {
+                    Ok(Response::UnlockFile { .. }) => Msg::FileUnlocked(entry.path),
+                    Ok(Response::Pending { .. }) => Msg::GetQuickAccess,
+                    r => failure_on("action-unlock", Some(entry.path), r),
+                });
+                false
+            }
+            Msg::FileLocked(s) => {
+                ConsoleService::log(format!("{} locked", s.path).as_str());
+                self.link.send_message(Msg::GetLockedFiles);
+                false
+            }
+            Msg::FileUnlocked(s) => {
+                ConsoleService::log(format!("{} unlocked", s).as_str());
+                self.link.send_message(Msg::GetLockedFiles);
+                false
+            }
+            Msg::UnlockAll => {
+                self.send(Request::PreviewUnlockMine, |r| match r {
+                    Ok(Response::PreviewUnlockMine { locks }) => Msg::UnlockPreviewReceived(locks),
+                    r => failure("action-list-your-locks", r),
+                });
+                false
+            }
+            Msg::UnlockPreviewReceived(locks) => {
+                self.unlock_preview = Some(locks);
+                true
+            }
+            Msg::ConfirmUnlockAll => {
+                let ids: Vec<u32> = self
+                    .unlock_preview
+                    .take()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|p| p.lock.id.parse().ok())
+                    .collect();
+                self.send(Request::UnlockMine { ids }, |r| match r {
+                    Ok(Response::UnlockMine { result }) => Msg::BatchDone(result),
+                    r => failure("action-unlock-your-locks", r),
+                });
+                true
+            }
+            Msg::CancelUnlockAll => {
+                self.unlock_preview = None;
+                true
+            }
+            Msg::ToggleStats => {
+                self.stats_open = !self.stats_open;
+                if self.stats_open {
+                    self.link.send_message(Msg::GetLockStats);
                 }
+                true
+            }
+            Msg::GetLockStats => {
+                let request = Request::GetLockStats {
+                    stale_days: self.stale_days,
+                };
+                self.send(request, |r| match r {
+                    Ok(Response::GetLockStats { stats }) => Msg::LockStatsReceived(stats),
+                    r => failure("action-get-lock-statistics", r),
+                });
                 false
             }
-            Msg::UnlockFile(v) => {
-                if self.locked_files.contains_key(&v.clone()) {
-                    let id = self.locked_files.get(&v).unwrap().1;
-                    self.link.send_future(async move {
-                        match unlock_file(id).await {
-                            Ok(s) => Msg::FileUnlocked(v),
-                            Err(_) => Msg::FileUnlocked(String::new()),
-                        }
-                    });
+            Msg::LockStatsReceived(stats) => {
+                self.stats = Some(stats);
+                true
+            }
+            Msg::StaleDaysChanged(days) => {
+                if let Ok(days) = days.parse() {
+                    self.stale_days = Some(days);
+                    self.link.send_message(Msg::GetLockStats);
                 }
                 false
             }
-            Msg::FileLocked(s) => {
-                if s.is_none() {
+            Msg::OpenExport => {
+                self.export_open = true;
+                self.exported = None;
+                true
+            }
+            Msg::ExportLocks(format) => {
+                let request = Request::ExportLocks {
+                    filter: self.filter.clone(),
+                    format,
+                    path: None,
+                };
+                self.send(request, |r| match r {
+                    Ok(Response::ExportLocks { path, count, .. }) => {
+                        Msg::LocksExported { path, count }
+                    }
+                    r => failure("action-export-locks", r),
+                });
+                false
+            }
+            Msg::LocksExported { path, count } => {
+                // `None` means the save dialog was cancelled, the dialog stays open
+                if let Some(path) = path {
+                    self.exported = Some(self.i18n.tr_args(
+                        "export-done",
+                        &[("count", count.into()), ("path", path.into())],
+                    ));
+                }
+                true
+            }
+            Msg::CloseExport => {
+                self.export_open = false;
+                true
+            }
+            Msg::PromptInput(answer) => {
+                self.prompt_answer = answer;
+                false
+            }
+            Msg::AnswerPrompt(submit) => {
+                if self.prompts.is_empty() {
+                    return false;
+                }
+                let (id, _, _) = self.prompts.remove(0);
+                let answer = std::mem::take(&mut self.prompt_answer);
+                let answer = Some(answer).filter(|_| submit);
+                self.send(Request::AnswerPrompt { id, answer }, |r| match r {
+                    Ok(Response::AnswerPrompt { id }) => Msg::PromptClosed(id),
+                    r => failure("action-answer-prompt", r),
+                });
+                true
+            }
+            Msg::PromptClosed(id) => {
+                let shown = self.prompts.first().map(|(p, _, _)| *p);
+                self.prompts.retain(|(p, _, _)| *p != id);
+                if shown == Some(id) {
+                    self.prompt_answer.clear();
+                }
+                true
+            }
+            Msg::CheckPushedLocks => {
+                self.send(Request::CheckPushedLocks, |r| match r {
+                    Ok(Response::CheckPushedLocks { pending, released }) => {
+                        Msg::PushedLocksReceived { pending, released }
+                    }
+                    r => failure("action-check-for-pushed-locks", r),
+                });
+                false
+            }
+            Msg::PushedLocksReceived { pending, released } => {
+                let kept = &self.kept_locks;
+                self.pushed_locks = pending
+                    .into_iter()
+                    .filter(|l| !kept.contains(&l.id))
+                    .collect();
+                if !released.done.is_empty() || !released.failed.is_empty() {
+                    self.link.send_message(Msg::BatchDone(released));
+                }
+                true
+            }
+            Msg::ConfirmRelease => {
+                let ids: Vec<u32> = std::mem::take(&mut self.pushed_locks)
+                    .iter()
+                    .filter_map(|l| l.id.parse().ok())
+                    .collect();
+                self.send(Request::UnlockMine { ids }, |r| match r {
+                    Ok(Response::UnlockMine { result }) => Msg::BatchDone(result),
+                    r => failure("action-unlock-pushed-locks", r),
+                });
+                true
+            }
+            Msg::DismissRelease => {
+                let pushed = std::mem::take(&mut self.pushed_locks);
+                self.kept_locks.extend(pushed.into_iter().map(|l| l.id));
+                true
+            }
+            Msg::GetQuickAccess => {
+                self.send(Request::GetQuickAccess, |r| match r {
+                    Ok(Response::GetQuickAccess {
+                        pinned,
+                        recent,
+                        watched,
+                        queued,
+                        pending,
+                    }) => Msg::QuickAccessReceived {
+                        pinned,
+                        recent,
+                        watched,
+                        queued,
+                        pending,
+                    },
+                    r => failure("action-get-pinned-and-recent-files", r),
+                });
+                false
+            }
+            Msg::QuickAccessReceived {
+                pinned,
+                recent,
+                watched,
+                queued,
+                pending,
+            } => {
+                self.pinned = pinned;
+                self.recent = recent;
+                self.watched = watched;
+                self.queued = queued;
+                self.pending = pending;
+                true
+            }
+            Msg::ToggleTreeView => {
+                self.tree_view = !self.tree_view;
+                if self.tree_view && !self.tree.contains_key("") {
+                    self.link.send_message(Msg::GetTree(String::new()));
+                }
+                true
+            }
+            Msg::GetTree(dir) => {
+                self.send(Request::GetTree { dir }, |r| match r {
+                    Ok(Response::GetTree { dir, nodes }) => Msg::TreeReceived { dir, nodes },
+                    r => failure("action-list-folder", r),
+                });
+                false
+            }
+            Msg::TreeReceived { dir, nodes } => {
+                self.tree.insert(dir, nodes);
+                true
+            }
+            Msg::ToggleDir(dir) => {
+                if !self.expanded.remove(&dir) {
+                    if !self.tree.contains_key(&dir) {
+                        self.link.send_message(Msg::GetTree(dir.clone()));
+                    }
+                    self.expanded.insert(dir);
+                }
+                true
+            }
+            Msg::LockFolder(dir) => {
+                self.batch_folder(dir, true);
+                false
+            }
+            Msg::UnlockFolder(dir) => {
+                self.batch_folder(dir, false);
+                false
+            }
+            Msg::BatchDone(result) => {
+                self.job = None;
+                self.batch_result = Some(result);
+                self.link.send_message(Msg::GetLockedFiles);
+                true
+            }
+            Msg::DismissReport => {
+                self.batch_result = None;
+                true
+            }
+            Msg::PinFile { path, pinned } => {
+                self.send(Request::PinFile { path, pinned }, |r| match r {
+                    Ok(Response::PinFile { .. }) => Msg::GetQuickAccess,
+                    r => failure("action-pin-file", r),
+                });
+                false
+            }
+            Msg::WatchFile { path, watched } => {
+                self.send(Request::WatchFile { path, watched }, |r| match r {
+                    Ok(Response::WatchFile { .. }) => Msg::GetQuickAccess,
+                    r => failure("action-watch-file", r),
+                });
+                false
+            }
+            Msg::QueueLock { path, queued } => {
+                self.send(Request::QueueLock { path, queued }, |r| match r {
+                    Ok(Response::QueueLock { .. }) => Msg::GetQuickAccess,
+                    r => failure("action-queue-lock", r),
+                });
+                false
+            }
+            Msg::CancelPending(path) => {
+                self.send(Request::CancelPending { path }, |r| match r {
+                    Ok(Response::CancelPending { .. }) => Msg::GetQuickAccess,
+                    r => failure("action-cancel-pending-operation", r),
+                });
+                false
+            }
+            Msg::GetInbox => {
+                if self.repo.is_empty() {
+                    return false;
+                }
+                self.send(Request::GetInbox, |r| match r {
+                    Ok(Response::GetInbox { events }) => Msg::InboxReceived(events),
+                    r => failure("action-get-inbox", r),
+                });
+                false
+            }
+            Msg::InboxReceived(events) => {
+                // the backend saw locks change, so the list is stale as well
+                if events.len() > self.inbox.len() {
+                    self.link.send_message(Msg::GetLockedFiles);
+                    self.link.send_message(Msg::GetQuickAccess);
+                }
+                self.inbox = events;
+                if self.inbox_open {
+                    self.inbox_seen = self.inbox.len();
+                }
+                true
+            }
+            Msg::ToggleInbox => {
+                self.inbox_open = !self.inbox_open;
+                self.inbox_seen = self.inbox.len();
+                true
+            }
+            Msg::ClearInbox => {
+                self.inbox.clear();
+                self.inbox_seen = 0;
+                self.send(Request::ClearInbox, |r| match r {
+                    Ok(Response::ClearInbox) => Msg::GetInbox,
+                    r => failure("action-clear-inbox", r),
+                });
+                true
+            }
+            Msg::Notify(message) => {
+                ConsoleService::log(&message);
+                self.notifications.push(message);
+                true
+            }
+            Msg::Failed {
+                action,
+                path,
+                result,
+            } => {
+                let path = path.unwrap_or_default();
+                let action = self.i18n.tr_args(action, &[("path", path.as_str().into())]);
+                let message = match result {
+                    Ok(r) => self.i18n.tr_args(
+                        "failed-unexpected",
+                        &[("action", action.as_str().into()), ("response", r.into())],
+                    ),
+                    Err(e) => self.i18n.tr_args(
+                        "failed",
+                        &[("action", action.as_str().into()), ("error", e.into())],
+                    ),
+                };
+                self.update(Msg::Notify(message))
+            }
+            Msg::DismissNotification(index) => {
+                if index < self.notifications.len() {
+                    self.notifications.remove(index);
+                }
+                true
+            }
+            Msg::BackendEvent(event) => match event {
+                api::Event::LockAdded { .. } | api::Event::LockRemoved { .. } => {
+                    self._reload = Some(TimeoutService::spawn(
+                        Duration::from_millis(300),
+                        self.link.callback(|_| Msg::ReloadLocks),
+                    ));
                     false
-                } else {
-                    let s = s.unwrap();
-                    ConsoleService::log(format!("{} locked", s.path).as_str());
-                    self.locked_files
-                        .insert(s.path, (s.owner.name, s.id.parse().unwrap()));
-                    self.link.send_future(async { Msg::GetLockedFiles });
+                }
+                api::Event::RepoChanged { repo } => {
+                    if repo != self.repo {
+                        self.link.send_message(Msg::RepoPicked { repo });
+                    }
+                    false
+                }
+                api::Event::CacheRebuilt { .. } => {
+                    if !self.filter.is_empty() {
+                        self.link
+                            .send_message(Msg::FilterChanged(self.filter.clone()));
+                    }
+                    false
+                }
+                api::Event::JobProgress { job, done, total } => {
+                    self.job = match done < total {
+                        true => Some((job, done, total)),
+                        false => None,
+                    };
+                    true
+                }
+                api::Event::Error { message } => {
+                    self.link.send_message(Msg::Notify(message));
+                    false
+                }
+                api::Event::CredentialPrompt { id, prompt, secret } => {
+                    self.prompts.push((id, prompt, secret));
+                    true
+                }
+                api::Event::PromptClosed { id } => self.update(Msg::PromptClosed(id)),
+                api::Event::ConnectionChanged { offline } => {
+                    self.offline = offline;
                     true
                 }
+                api::Event::PendingReplayed { result } => {
+                    self.batch_result = Some(result);
+                    self.link.send_message(Msg::GetLockedFiles);
+                    true
+                }
+            },
+            Msg::ReloadLocks => {
+                self._reload = None;
+                self.link.send_message(Msg::GetLockedFiles);
+                if self.stats_open {
+                    self.link.send_message(Msg::GetLockStats);
+                }
+                false
             }
-            Msg::FileUnlocked(s) => {
-                ConsoleService::log(format!("{} unlocked", s).as_str());
-                self.locked_files.remove(&s);
-                self.link.send_future(async { Msg::GetLockedFiles });
-                true
+            Msg::KeyDown(event) => {
+                let combo = keys::combo(&event);
+                if !self.prompts.is_empty() {
+                    match combo.as_str() {
+                        "enter" => self.link.send_message(Msg::AnswerPrompt(true)),
+                        "escape" => self.link.send_message(Msg::AnswerPrompt(false)),
+                        // typing the answer
+                        _ => return false,
+                    }
+                    event.prevent_default();
+                    return false;
+                }
+                if self.palette_open {
+                    let count = self.palette_commands().len();
+                    match combo.as_str() {
+                        "escape" => self.palette_open = false,
+                        "arrowdown" => {
+                            self.palette_selected =
+                                (self.palette_selected + 1).min(count.max(1) - 1)
+                        }
+                        "arrowup" => {
+                            self.palette_selected = self.palette_selected.saturating_sub(1)
+                        }
+                        "enter" => {
+                            if let Some((command, _)) =
+                                self.palette_commands().get(self.palette_selected)
+                            {
+                                self.link.send_message(Msg::RunCommand(*command));
+                            }
+                            self.palette_open = false;
+                        }
+                        // typing into the palette
+                        _ => return false,
+                    }
+                    event.prevent_default();
+                    return true;
+                }
+                if keys::in_text_field(&event) && keys::is_typing(&combo) {
+                    return false;
+                }
+                let command = self
+                    .key_bindings
+                    .iter()
+                    .find(|(_, k)| !k.is_empty() && keys::normalize(k) == combo)
+                    .map(|(c, _)| *c);
+                match command {
+                    Some(command) => {
+                        event.prevent_default();
+                        self.update(Msg::RunCommand(command))
+                    }
+                    None => false,
+                }
             }
-            Msg::UnlockAll => {
-                for (k, v) in self.locked_files.iter() {
-                    let k = k.clone();
-                    self.link.send_future(async move { Msg::UnlockFile(k) });
+            Msg::RunCommand(command) => {
+                self.palette_open = false;
+                match command {
+                    api::Command::OpenPalette => {
+                        self.palette_open = true;
+                        self.palette_filter.clear();
+                        self.palette_selected = 0;
+                        self.focus_palette = true;
+                    }
+                    api::Command::FocusSearch => {
+                        if let Some(input) = self.search_ref.cast::<web_sys::HtmlElement>() {
+                            let _ = input.focus();
+                        }
+                    }
+                    api::Command::SelectNext => self.move_selection(1),
+                    api::Command::SelectPrevious => self.move_selection(-1),
+                    api::Command::ToggleLock => {
+                        if let Some(msg) = self.selected_action() {
+                            self.link.send_message(msg);
+                        }
+                    }
+                    api::Command::Refresh => self.link.send_message(Msg::ForceRefresh),
+                    api::Command::PickRepo => self.link.send_message(Msg::PickRepo),
+                    api::Command::ToggleTreeView => self.link.send_message(Msg::ToggleTreeView),
+                    api::Command::ToggleInbox => self.link.send_message(Msg::ToggleInbox),
+                    api::Command::UnlockAllMine => self.link.send_message(Msg::UnlockAll),
+                    api::Command::ExportLocks => self.link.send_message(Msg::OpenExport),
+                    api::Command::ToggleStats => self.link.send_message(Msg::ToggleStats),
                 }
+                true
+            }
+            Msg::GetKeyBindings => {
+                self.send(Request::GetKeyBindings, |r| match r {
+                    Ok(Response::GetKeyBindings { bindings }) => Msg::KeyBindingsReceived(bindings),
+                    r => failure("action-get-key-bindings", r),
+                });
                 false
             }
+            Msg::KeyBindingsReceived(bindings) => {
+                self.key_bindings = bindings;
+                true
+            }
+            Msg::LocaleReceived(locale) => {
+                let locale = match locale.is_empty() {
+                    true => i18n::system_locale(),
+                    false => locale,
+                };
+                self.i18n = I18n::new(&locale);
+                true
+            }
+            Msg::PaletteFilterChanged(filter) => {
+                self.palette_filter = filter;
+                self.palette_selected = 0;
+                true
+            }
+            Msg::ClosePalette => {
+                self.palette_open = false;
+                true
+            }
+        }
+    }
+
+    fn rendered(&mut self, _first_render: bool) {
+        if self.focus_palette {
+            self.focus_palette = false;
+            if let Some(input) = self.palette_ref.cast::<web_sys::HtmlElement>() {
+                let _ = input.focus();
+            }
+        }
+        // a new prompt renders a new input, the old one had the focus
+        if let Some(input) = self.prompt_ref.cast::<web_sys::HtmlElement>() {
+            if self.prompt_answer.is_empty() {
+                let _ = input.focus();
+            }
         }
     }
 
@@ -368,119 +1394,437 @@ impl Component for Model {
     }
 
     fn view(&self) -> Html {
-        let filtered_list_item = |f: &String| {
-            let is_locked = self.locked_files.contains_key(&f.clone());
-            let locked_by = match self.locked_files.get(&f.clone()) {
-                None => "",
-                Some(v) => &v.0,
+        let pin_button = |path: &str| {
+            let pinned = self.pinned.iter().any(|p| p.path == path);
+            let event = Msg::PinFile {
+                path: path.to_string(),
+                pinned: !pinned,
             };
-            let (button_text, button_type, event) = if is_locked {
-                (
-                    "Unlock",
+            html! {
+                <button class="pure-button pin" title=self.i18n.tr(if pinned { "unpin" } else { "pin" }) onclick=self.link.callback(move |_| event.clone())>
+                    { if pinned { "★" } else { "☆" } }
+                </button>
+            }
+        };
+
+        let watch_button = |path: &str| {
+            let watched = self.watched.iter().any(|w| w.path == path);
+            let event = Msg::WatchFile {
+                path: path.to_string(),
+                watched: !watched,
+            };
+            html! {
+                <button class={ if watched { "pure-button pin watching" } else { "pure-button pin" } } title=self.i18n.tr(if watched { "stop-watching" } else { "watch" }) onclick=self.link.callback(move |_| event.clone())>
+                    { "\u{1F441}" }
+                </button>
+            }
+        };
+
+        let queue_button = |r: &api::SearchResult| {
+            let queued = self.queued.iter().any(|q| q.path == r.path);
+            if r.lock.is_none() && !queued {
+                return html! {};
+            }
+            let event = Msg::QueueLock {
+                path: r.path.clone(),
+                queued: !queued,
+            };
+            html! {
+                <button class="pure-button pin" title=self.i18n.tr(if queued { "cancel-queue-title" } else { "queue-title" }) onclick=self.link.callback(move |_| event.clone())>
+                    { self.i18n.tr(if queued { "cancel-queue" } else { "queue" }) }
+                </button>
+            }
+        };
+
+        let filtered_list_item = |r: &api::SearchResult, selected: bool| {
+            let locked_by = match &r.lock {
+                None => html! {},
+                Some(l) => self.locked_by(l),
+            };
+            let (button_text, button_type, event) = match &r.lock {
+                Some(l) => (
+                    self.i18n.tr("unlock"),
                     "pure-button button-success",
-                    Msg::UnlockFile(f.clone()),
-                )
-            } else {
-                (
-                    "Lock",
+                    Msg::UnlockFile(l.clone()),
+                ),
+                None => (
+                    self.i18n.tr("lock"),
                     "pure-button pure-button-primary",
-                    Msg::LockFile(f.clone()),
-                )
+                    Msg::LockFile(r.path.clone()),
+                ),
+            };
+
+            // lockable files are expected to be lfs objects and the other way around
+            let tag_class = match r.lfs == r.lockable {
+                true => "tag",
+                false => "tag tag-warning",
             };
-            let path = f.split_whitespace().next().unwrap();
 
             html! {
-                <tr>
-                    <td>{ f }</td>
+                <tr class=if selected { "selected" } else { "" }>
+                    <td>
+                        { highlighted(&r.path, &r.indices) }
+                        <span class=tag_class>{ self.i18n.tr(if r.lfs { "tag-lfs" } else { "tag-not-lfs" }) }</span>
+                        <span class=tag_class>{ self.i18n.tr(if r.lockable { "tag-lockable" } else { "tag-not-lockable" }) }</span>
+                    </td>
                     <td>{locked_by}</td>
                     <td class={"center"}>
+                        { pin_button(&r.path) }
+                        { watch_button(&r.path) }
+                        { queue_button(r) }
                         <button class={button_type} onclick=self.link.callback(move |_|{event.clone()})>{button_text}</button>
                     </td>
                 </tr>
             }
         };
 
-        let locked_list_item = |f: &String| {
-            let locked_by = match self.locked_files.get(&f.clone()) {
-                None => "",
-                Some(v) => &v.0,
-            };
-            let f = f.clone();
+        let locked_list_item = |l: &api::LockEntry, selected: bool| {
+            let entry = l.clone();
             html! {
-                <tr>
-                    <td>{ f.clone() }</td>
-                    <td>{locked_by}</td>
+                <tr class=if selected { "selected" } else { "" }>
+                    <td>{ &l.path }</td>
+                    <td>{ self.locked_by(l) }</td>
                     <td class={"center"}>
-                        <button class={"pure-button button-success"} onclick=self.link.callback(move |_|{Msg::UnlockFile(f.clone())})>{"Unlock"}</button>
+                        <button class={"pure-button button-success"} onclick=self.link.callback(move |_|{Msg::UnlockFile(entry.clone())})>{ self.i18n.tr("unlock") }</button>
                     </td>
                 </tr>
             }
         };
 
-        let table = match self.list_type {
-            ListType::LockedFiles => {
-                html! {
-                <div>
-                    <table class="pure-table">
-                        <thead>
+        let quick_access_section = |title: &str, entries: &[api::SearchResult]| match entries
+            .is_empty()
+        {
+            true => html! {},
+            false => html! {
+                <>
+                    <tr class="quick-access-title"><td colspan="3">{ self.i18n.tr(title) }</td></tr>
+                    { for entries.iter().map(|r| filtered_list_item(r, false)) }
+                </>
+            },
+        };
+        let pending_section = match self.pending.is_empty() {
+            true => html! {},
+            false => html! {
+                <>
+                    <tr class="quick-access-title"><td colspan="3">{ self.i18n.tr("section-pending") }</td></tr>
+                    { for self.pending.iter().map(|o| {
+                        let path = o.path.clone();
+                        let time = self.i18n.time(&o.time);
+                        html! {
                             <tr>
-                                <th>{"File Name"}</th>
-                                <th>{"Locked By"}</th>
-                                <th>{"Action"}</th>
+                                <td>{ &o.path }</td>
+                                <td>{ self.i18n.tr_args(if o.lock { "pending-lock" } else { "pending-unlock" }, &[("time", time.into())]) }</td>
+                                <td class={"center"}>
+                                    <button class="pure-button" onclick=self.link.callback(move |_| Msg::CancelPending(path.clone()))>{ self.i18n.tr("cancel-pending") }</button>
+                                </td>
                             </tr>
-                        </thead>
+                        }
+                    }) }
+                </>
+            },
+        };
+        let quick_access = match self.list_type {
+            ListType::LockedFiles
+                if !self.pinned.is_empty()
+                    || !self.recent.is_empty()
+                    || !self.watched.is_empty()
+                    || !self.queued.is_empty()
+                    || !self.pending.is_empty() =>
+            {
+                html! {
+                <div class="quick-access">
+                    <table class="pure-table">
                         <tbody>
-                            { for self.locked_files.iter().map(|(v, _)|{v}).map(locked_list_item) }
+                            { quick_access_section("section-pinned", &self.pinned) }
+                            { quick_access_section("section-recent", &self.recent) }
+                            { quick_access_section("section-watching", &self.watched) }
+                            { pending_section }
+                            { quick_access_section("section-queued", &self.queued) }
                         </tbody>
                     </table>
                 </div>
                 }
             }
-            ListType::SearchResult => {
+            _ => html! {},
+        };
+
+        let rows = match self.list_type {
+            ListType::LockedFiles => self.virtual_rows(&self.locked_files, locked_list_item),
+            ListType::SearchResult => self.virtual_rows(&self.filtered_files, filtered_list_item),
+        };
+        let header = html! {
+            <thead>
+                <tr>
+                    <th>{ self.i18n.tr("column-file") }</th>
+                    <th>{ self.i18n.tr("column-locked-by") }</th>
+                    <th>{ self.i18n.tr("column-action") }</th>
+                </tr>
+            </thead>
+        };
+        let batch_report = match &self.batch_result {
+            None => html! {},
+            Some(result) => html! {
+                <div class="batch-report">
+                    <button class="pure-button dismiss" onclick=self.link.callback(|_| Msg::DismissReport)>{ "\u{2715}" }</button>
+                    { self.i18n.tr_args("batch-report", &[("done", result.done.len().into()), ("failed", result.failed.len().into())]) }
+                    <ul class="batch-done">
+                        { for result.done.iter().map(|path| html! { <li>{ path }</li> }) }
+                    </ul>
+                    <ul>
+                        { for result.failed.iter().map(|f| html! { <li>{ format!("{}: {}", f.path, f.error) }</li> }) }
+                    </ul>
+                </div>
+            },
+        };
+        let job = match &self.job {
+            None => html! {},
+            Some((job, done, total)) => html! {
+                <div class="batch-report">
+                    { self.i18n.tr_args("job-progress", &[("job", job.as_str().into()), ("done", self.i18n.number(*done).into()), ("total", self.i18n.number(*total).into())]) }
+                    <progress value=done.to_string() max=total.to_string()></progress>
+                </div>
+            },
+        };
+        let offline = match &self.offline {
+            None => html! {},
+            Some(offline) => {
+                let text = match &offline.since {
+                    None => self.i18n.tr("offline-local"),
+                    Some(since) => {
+                        // the time alone is ambiguous for data from another day
+                        let today = js_sys::Date::new_0().to_date_string();
+                        let time = match js_sys::Date::new(&since.as_str().into()).to_date_string()
+                            == today
+                        {
+                            true => self.i18n.time(since),
+                            false => self.i18n.date_time(since),
+                        };
+                        self.i18n.tr_args("offline-since", &[("time", time.into())])
+                    }
+                };
                 html! {
-                    <div>
-                        <table class="pure-table">
-                            <thead>
-                                <tr>
-                                    <th>{"File Name"}</th>
-                                    <th>{"Locked By"}</th>
-                                    <th>{"Action"}</th>
-                                </tr>
-                            </thead>
-                            <tbody>
-                                { for self.filtered_files.iter().map(filtered_list_item) }
-                            </tbody>
-                        </table>
+                    <div class="offline" title=offline.error.clone()>{ text }</div>
+                }
+            }
+        };
+        let notifications = html! {
+            <div class="notifications">
+                { for self.notifications.iter().enumerate().map(|(i, n)| html! {
+                    <div class="notification">
+                        <button class="pure-button dismiss" onclick=self.link.callback(move |_| Msg::DismissNotification(i))>{ "\u{2715}" }</button>
+                        { n }
+                    </div>
+                }) }
+            </div>
+        };
+        let inbox = match self.inbox_open {
+            false => html! {},
+            true => html! {
+                <div class="inbox">
+                    <button class="pure-button dismiss" onclick=self.link.callback(|_| Msg::ClearInbox)>{ self.i18n.tr("inbox-clear") }</button>
+                    { match self.inbox.is_empty() {
+                        true => html! { <div>{ self.i18n.tr("inbox-empty") }</div> },
+                        false => html! {
+                            <ul>
+                                { for self.inbox.iter().rev().map(|e| html! {
+                                    <li>
+                                        <span class="inbox-time">{ self.i18n.time(&e.time) }</span>
+                                        { self.describe(e) }
+                                    </li>
+                                }) }
+                            </ul>
+                        },
+                    } }
+                </div>
+            },
+        };
+        let unread = self.inbox.len().saturating_sub(self.inbox_seen);
+        let unlock_dialog = match &self.unlock_preview {
+            None => html! {},
+            Some(locks) => {
+                let modified = locks.iter().filter(|l| l.modified).count();
+                html! {
+                    <div class="dialog-backdrop">
+                        <div class="dialog">
+                            <h3>{ self.i18n.tr_args("unlock-preview", &[("count", locks.len().into())]) }</h3>
+                            { match modified {
+                                0 => html! {},
+                                n => html! {
+                                    <p class="warning">{ self.i18n.tr_args("unlock-preview-modified", &[("count", n.into())]) }</p>
+                                },
+                            } }
+                            <ul class="dialog-list">
+                                { for locks.iter().map(|l| html! {
+                                    <li>
+                                        { &l.lock.path }
+                                        { if l.modified { html! { <span class="tag tag-warning">{ self.i18n.tr("tag-modified") }</span> } } else { html! {} } }
+                                    </li>
+                                }) }
+                            </ul>
+                            <button class="pure-button button-success" disabled=locks.is_empty() onclick=self.link.callback(|_| Msg::ConfirmUnlockAll)>{ self.i18n.tr("unlock") }</button>
+                            <button class="pure-button" onclick=self.link.callback(|_| Msg::CancelUnlockAll)>{ self.i18n.tr("cancel") }</button>
+                        </div>
                     </div>
                 }
             }
         };
+        let release_dialog = match self.pushed_locks.is_empty() {
+            true => html! {},
+            false => html! {
+                <div class="dialog-backdrop">
+                    <div class="dialog">
+                        <h3>{ self.i18n.tr_args("release-pushed", &[("count", self.pushed_locks.len().into())]) }</h3>
+                        <ul class="dialog-list">
+                            { for self.pushed_locks.iter().map(|l| html! { <li>{ &l.path }</li> }) }
+                        </ul>
+                        <button class="pure-button button-success" onclick=self.link.callback(|_| Msg::ConfirmRelease)>{ self.i18n.tr("unlock") }</button>
+                        <button class="pure-button" onclick=self.link.callback(|_| Msg::DismissRelease)>{ self.i18n.tr("keep-locked") }</button>
+                    </div>
+                </div>
+            },
+        };
+        let export_dialog = match self.export_open {
+            false => html! {},
+            true => html! {
+                <div class="dialog-backdrop">
+                    <div class="dialog">
+                        <h3>{ self.i18n.tr("export-title") }</h3>
+                        <p>{ match self.filter.is_empty() {
+                            true => self.i18n.tr("export-all"),
+                            false => self.i18n.tr_args("export-filtered", &[("filter", self.filter.as_str().into())]),
+                        } }</p>
+                        { for api::ExportFormat::ALL.iter().map(|&format| html! {
+                            <button class="pure-button pure-button-primary" onclick=self.link.callback(move |_| Msg::ExportLocks(format))>
+                                { self.i18n.tr(&format!("export-{}", format.extension())) }
+                            </button>
+                        }) }
+                        <button class="pure-button" onclick=self.link.callback(|_| Msg::CloseExport)>{ self.i18n.tr("close") }</button>
+                        { match &self.exported {
+                            Some(message) => html! { <p>{ message }</p> },
+                            None => html! {},
+                        } }
+                    </div>
+                </div>
+            },
+        };
+        let prompt = match self.prompts.first() {
+            None => html! {},
+            Some((id, prompt, secret)) => html! {
+                <div class="dialog-backdrop">
+                    <div class="dialog">
+                        <h3>{ self.i18n.tr("prompt-title") }</h3>
+                        <p class="prompt-text">{ prompt }</p>
+                        <input key=id.to_string() type={ if *secret { "password" } else { "text" } } class="prompt-input" ref=self.prompt_ref.clone() value={&self.prompt_answer} oninput=self.link.callback(|e: InputData| Msg::PromptInput(e.value))/>
+                        <button class="pure-button pure-button-primary" onclick=self.link.callback(|_| Msg::AnswerPrompt(true))>{ self.i18n.tr("prompt-ok") }</button>
+                        <button class="pure-button" onclick=self.link.callback(|_| Msg::AnswerPrompt(false))>{ self.i18n.tr("cancel") }</button>
+                    </div>
+                </div>
+            },
+        };
+        let palette = match self.palette_open {
+            false => html! {},
+            true => html! {
+                <div class="dialog-backdrop">
+                    <div class="dialog palette">
+                        <input type="text" class="pure-input-1" ref=self.palette_ref.clone() value={&self.palette_filter} placeholder=self.i18n.tr("palette-placeholder") oninput=self.link.callback(|e: InputData| Msg::PaletteFilterChanged(e.value))/>
+                        <ul class="palette-list">
+                            { for self.palette_commands().into_iter().enumerate().map(|(i, (command, keys))| {
+                                let command = *command;
+                                html! {
+                                    <li class=if i == self.palette_selected { "selected" } else { "" } onclick=self.link.callback(move |_| Msg::RunCommand(command))>
+                                        { self.command_title(command) }
+                                        <span class="keys">{ keys }</span>
+                                    </li>
+                                }
+                            }) }
+                        </ul>
+                        <button class="pure-button" onclick=self.link.callback(|_| Msg::ClosePalette)>{ self.i18n.tr("close") }</button>
+                    </div>
+                </div>
+            },
+        };
+        let table = match (&self.list_type, self.tree_view) {
+            (ListType::LockedFiles, true) => html! {
+                <div class="tree">
+                    <table class="pure-table">
+                        { header }
+                        <tbody>
+                            { self.tree_rows("", 0) }
+                        </tbody>
+                    </table>
+                </div>
+            },
+            _ => html! {
+            <div class="virtual-table" ref=self.table_ref.clone() onscroll=self.link.callback(|e: Event| {
+                let table = e.target().unwrap().unchecked_into::<web_sys::Element>();
+                Msg::Scrolled { top: table.scroll_top(), height: table.client_height() }
+            })>
+                <table class="pure-table">
+                    { header }
+                    <tbody>
+                        { rows }
+                    </tbody>
+                </table>
+            </div>
+            },
+        };
 
         html! {
-        <div>
+        <div class="app">
             <div class={"pure-g"}>
-                <div class="pure-u-1-5">
-                    <button class={"pure-button button-warning"} onclick=self.link.callback(|_| Msg::PickRepo)>{ "Pick Repo" }</button>
+                <div class="pure-u-1-6">
+                    <button class={"pure-button button-warning"} onclick=self.link.callback(|_| Msg::PickRepo)>{ self.i18n.tr("pick-repo") }</button>
                 </div>
-                <div class="pure-u-2-5">
+                <div class="pure-u-1-6">
                     <button class={"pure-button pure-button-disabled"} onclick=self.link.callback(|_| Msg::PickRepo)>{ &self.repo }</button>
                 </div>
-                <div class="pure-u-1-5">
-                    <button class={"pure-button button-primary"} onclick=self.link.callback(|_| Msg::GetLockedFiles)>{ "Force Refresh Locked Files" }</button>
+                <div class="pure-u-1-6">
+                    <button class={"pure-button button-primary"} onclick=self.link.callback(|_| Msg::ForceRefresh)>{ self.i18n.tr("force-refresh") }</button>
+                </div>
+                <div class="pure-u-1-6">
+                    <button class={"pure-button"} onclick=self.link.callback(|_| Msg::ToggleTreeView)>{ self.i18n.tr(if self.tree_view { "list-view" } else { "tree-view" }) }</button>
+                </div>
+                <div class="pure-u-1-6">
+                    <button class={ if unread > 0 { "pure-button button-warning" } else { "pure-button" } } onclick=self.link.callback(|_| Msg::ToggleInbox)>{ self.i18n.tr_args("inbox", &[("unread", unread.into())]) }</button>
+                </div>
+                <div class="pure-u-1-6">
+                    <button class={ if self.stats_open { "pure-button pure-button-active" } else { "pure-button" } } onclick=self.link.callback(|_| Msg::ToggleStats)>{ self.i18n.tr("stats") }</button>
+                    <button class={"pure-button"} onclick=self.link.callback(|_| Msg::OpenExport)>{ self.i18n.tr("export") }</button>
                 </div>
             </div>
             <div class={"pure-g"}>
             <div class="pure-u-1">
                 <form class="pure-form">
                  <fieldset>
-                    <input type="text" value={&self.filter} class={"pure-input-2-3"} placeholder="Type Here" oninput=self.link.callback(|e: InputData| Msg::FilterChanged(e.value))/>
-                    <button type="button" class={"pure-button button-secondary pure-input-1-3"} onclick=self.link.callback(|_| Msg::UnlockAll)>{ "Unlock All" }</button>
+                    <input type="text" ref=self.search_ref.clone() value={&self.filter} class={"pure-input-1-2"} placeholder=self.i18n.tr("search-placeholder") oninput=self.link.callback(|e: InputData| Msg::FilterChanged(e.value))/>
+                    <label class="all-files">
+                        <input type="checkbox" checked=self.all_files onclick=self.link.callback(|_| Msg::ToggleAllFiles)/>
+                        { " " }{ self.i18n.tr("all-files") }
+                    </label>
+                    <button type="button" class={"pure-button button-secondary pure-input-1-3"} onclick=self.link.callback(|_| Msg::UnlockAll)>{ self.i18n.tr("unlock-all-mine") }</button>
                  </fieldset>
                 </form>
+                { match &self.filter_error {
+                    Some(e) => html! { <div class="filter-error">{ e }</div> },
+                    None => html! {},
+                } }
             </div>
 
             </div>
-             {table}
+             {offline}
+             {notifications}
+             {unlock_dialog}
+             {release_dialog}
+             {export_dialog}
+             {prompt}
+             {palette}
+             {job}
+             {batch_report}
+             {inbox}
+             { match self.stats_open {
+                 true => self.dashboard(),
+                 false => html! { <>{quick_access}{table}</> },
+             } }
         </div>
         }
     }
@@ -0,0 +1,53 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+pub const PAGE_SIZE: usize = 100;
+
+/// A list fetched from the backend one page at a time.
+pub struct PagedList<T> {
+    pub total: usize,
+    pages: HashMap<usize, Vec<T>>,
+    requested: HashSet<usize>,
+}
+
+impl<T> PagedList<T> {
+    pub fn new() -> Self {
+        Self {
+            total: 0,
+            pages: HashMap::new(),
+            requested: HashSet::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.total = 0;
+        self.pages.clear();
+        self.requested.clear();
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.pages
+            .get(&(index / PAGE_SIZE))
+            .and_then(|p| p.get(index % PAGE_SIZE))
+    }
+
+    pub fn insert(&mut self, offset: usize, items: Vec<T>, total: usize) {
+        self.total = total;
+        self.pages.insert(offset / PAGE_SIZE, items);
+    }
+
+    /// Pages covering `rows` that are neither loaded nor requested yet, as
+    /// offsets. They are marked as requested.
+    pub fn missing(&mut self, rows: Range<usize>) -> Vec<usize> {
+        let mut missing = Vec::new();
+        if rows.start >= rows.end {
+            return missing;
+        }
+        for page in rows.start / PAGE_SIZE..=(rows.end - 1) / PAGE_SIZE {
+            if !self.pages.contains_key(&page) && self.requested.insert(page) {
+                missing.push(page * PAGE_SIZE);
+            }
+        }
+        missing
+    }
+}
@@ -0,0 +1,387 @@
+//! In-memory implementation of the Git LFS file locking api, for development and
+//! integration tests.
+//!
+//! Point a repository at it with `git config lfs.url <MockServer::url>`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Owner {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Lock {
+    pub id: String,
+    pub path: String,
+    pub owner: Owner,
+    pub locked_at: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Accepted `(name, password)` pairs. When empty every request is
+    /// anonymous and locks are owned by `anonymous`.
+    pub users: Vec<(String, String)>,
+    /// Delay applied before answering each request.
+    pub latency: Duration,
+    /// Answer every nth request with a 500.
+    pub fail_every: Option<u32>,
+    /// Splits lock lists and verify answers into pages of this many locks. Their
+    /// cursors contain characters that must be escaped in a query string.
+    pub page_size: Option<usize>,
+}
+
+#[derive(Default)]
+struct Store {
+    locks: Vec<Lock>,
+    next_id: u32,
+    request_count: u32,
+    /// Pending injected failures, as `(remaining, status)`.
+    fail_next: Option<(u32, u16)>,
+}
+
+#[derive(Deserialize)]
+struct CreateLock {
+    path: String,
+}
+
+#[derive(Deserialize, Default)]
+struct VerifyBody {
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct UnlockBody {
+    #[serde(default)]
+    force: bool,
+}
+
+type Reply = tiny_http::Response<Cursor<Vec<u8>>>;
+
+fn reply(status: u16, body: serde_json::Value) -> Reply {
+    tiny_http::Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(
+            tiny_http::Header::from_bytes(
+                &b"Content-Type"[..],
+                &b"application/vnd.git-lfs+json"[..],
+            )
+            .unwrap(),
+        )
+}
+
+fn message(status: u16, message: &str) -> Reply {
+    reply(status, serde_json::json!({ "message": message }))
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    store: Arc<Mutex<Store>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts the server on a random local port.
+    pub fn start(config: Config) -> std::io::Result<Self> {
+        Self::start_on(config, 0)
+    }
+
+    pub fn start_on(config: Config, port: u16) -> std::io::Result<Self> {
+        let server = tiny_http::Server::http(("127.0.0.1", port))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+        let addr = server.server_addr();
+        let store = Arc::new(Mutex::new(Store {
+            next_id: 1,
+            ..Default::default()
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let store = store.clone();
+            let stop = stop.clone();
+            let config = Arc::new(config);
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    if let Ok(Some(request)) = server.recv_timeout(Duration::from_millis(100)) {
+                        let config = config.clone();
+                        let store = store.clone();
+                        std::thread::spawn(move || handle(&config, &store, request));
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            store,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn locks(&self) -> Vec<Lock> {
+        self.store.lock().unwrap().locks.clone()
+    }
+
+    /// Adds a lock directly, e.g. to simulate another user holding a file.
+    pub fn insert_lock(&self, path: &str, owner: &str) -> Lock {
+        let mut store = self.store.lock().unwrap();
+        let lock = new_lock(&mut store, path, owner);
+        store.locks.push(lock.clone());
+        lock
+    }
+
+    /// Drops the lock on `path` directly, e.g. to simulate another user releasing it.
+    pub fn remove_lock(&self, path: &str) -> Option<Lock> {
+        let mut store = self.store.lock().unwrap();
+        let index = store.locks.iter().position(|l| l.path == path)?;
+        Some(store.locks.remove(index))
+    }
+
+    /// Makes the next `count` requests fail with `status`.
+    pub fn fail_next(&self, count: u32, status: u16) {
+        self.store.lock().unwrap().fail_next = match count {
+            0 => None,
+            n => Some((n, status)),
+        };
+    }
+
+    /// Blocks until the server is stopped, which never happens unless the
+    /// serving thread panics.
+    pub fn wait(mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn new_lock(store: &mut Store, path: &str, owner: &str) -> Lock {
+    let id = store.next_id;
+    store.next_id += 1;
+    Lock {
+        id: id.to_string(),
+        path: path.to_string(),
+        owner: Owner {
+            name: owner.to_string(),
+        },
+        locked_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    }
+}
+
+/// Returns the authenticated user name, `None` if credentials are missing or wrong.
+fn authenticate(config: &Config, request: &tiny_http::Request) -> Option<String> {
+    if config.users.is_empty() {
+        return Some("anonymous".to_string());
+    }
+    let header = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))?;
+    let encoded = header.value.as_str().strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(base64::decode(encoded.trim()).ok()?).ok()?;
+    let mut parts = decoded.splitn(2, ':');
+    let (name, password) = (parts.next()?, parts.next()?);
+    config
+        .users
+        .iter()
+        .find(|(n, p)| n == name && p == password)
+        .map(|(n, _)| n.clone())
+}
+
+/// The page of `locks` starting at `cursor`, and the cursor of the next one.
+fn page<'a>(
+    config: &Config,
+    locks: Vec<&'a Lock>,
+    cursor: Option<&str>,
+) -> (Vec<&'a Lock>, Option<String>) {
+    let start: usize = cursor
+        .and_then(|c| c.split(' ').next()?.parse().ok())
+        .unwrap_or(0)
+        .min(locks.len());
+    let end = match config.page_size {
+        Some(size) => (start + size).min(locks.len()),
+        None => locks.len(),
+    };
+    let next = Some(format!("{} & more", end)).filter(|_| end < locks.len());
+    (locks[start..end].to_vec(), next)
+}
+
+fn query_params(url: &str) -> HashMap<String, String> {
+    url.splitn(2, '?')
+        .nth(1)
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|pair| {
+            let mut kv = pair.splitn(2, '=');
+            let key = kv.next()?;
+            let value = kv.next().unwrap_or("");
+            Some((key.to_string(), percent_decode(value)))
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => out.push(b),
+                    Err(_) => out.extend_from_slice(&bytes[i..i + 3]),
+                }
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn handle(config: &Config, store: &Mutex<Store>, mut request: tiny_http::Request) {
+    std::thread::sleep(config.latency);
+    let response = respond(config, store, &mut request);
+    if let Err(e) = request.respond(response) {
+        println!("failed to send response, {}", e);
+    }
+}
+
+fn respond(config: &Config, store: &Mutex<Store>, request: &mut tiny_http::Request) -> Reply {
+    {
+        let mut store = store.lock().unwrap();
+        store.request_count += 1;
+        if let Some((remaining, status)) = store.fail_next.take() {
+            if remaining > 1 {
+                store.fail_next = Some((remaining - 1, status));
+            }
+            return message(status, "injected failure");
+        }
+        if let Some(n) = config.fail_every {
+            if n > 0 && store.request_count % n == 0 {
+                return message(500, "injected failure");
+            }
+        }
+    }
+
+    let user = match authenticate(config, request) {
+        Some(user) => user,
+        None => {
+            return message(401, "credentials needed").with_header(
+                tiny_http::Header::from_bytes(
+                    &b"LFS-Authenticate"[..],
+                    &b"Basic realm=\"mock\""[..],
+                )
+                .unwrap(),
+            )
+        }
+    };
+
+    let mut body = String::new();
+    if request.as_reader().read_to_string(&mut body).is_err() {
+        return message(400, "unreadable body");
+    }
+
+    let url = request.url().to_string();
+    let path = url.splitn(2, '?').next().unwrap().trim_end_matches('/');
+    // clients may use `lfs.url` with or without a trailing `info/lfs`
+    let path = path.strip_prefix("/info/lfs").unwrap_or(path);
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let mut store = store.lock().unwrap();
+
+    match (request.method(), segments.as_slice()) {
+        (tiny_http::Method::Get, ["locks"]) => {
+            let params = query_params(&url);
+            let locks: Vec<&Lock> = store
+                .locks
+                .iter()
+                .filter(|l| {
+                    params
+                        .get("path")
+                        .map_or(true, |p| p.is_empty() || *p == l.path)
+                })
+                .filter(|l| {
+                    params
+                        .get("id")
+                        .map_or(true, |id| id.is_empty() || *id == l.id)
+                })
+                .collect();
+            let (locks, next_cursor) =
+                page(config, locks, params.get("cursor").map(String::as_str));
+            reply(
+                200,
+                serde_json::json!({ "locks": locks, "next_cursor": next_cursor }),
+            )
+        }
+        (tiny_http::Method::Post, ["locks"]) => {
+            let create: CreateLock = match serde_json::from_str(&body) {
+                Ok(c) => c,
+                Err(e) => return message(400, &e.to_string()),
+            };
+            if let Some(existing) = store.locks.iter().find(|l| l.path == create.path) {
+                return reply(
+                    409,
+                    serde_json::json!({ "lock": existing, "message": "already created lock" }),
+                );
+            }
+            let lock = new_lock(&mut store, &create.path, &user);
+            store.locks.push(lock.clone());
+            reply(201, serde_json::json!({ "lock": lock }))
+        }
+        (tiny_http::Method::Post, ["locks", "verify"]) => {
+            let verify: VerifyBody = serde_json::from_str(&body).unwrap_or_default();
+            let locks = store.locks.iter().collect();
+            let (locks, next_cursor) = page(config, locks, verify.cursor.as_deref());
+            let (ours, theirs): (Vec<&Lock>, Vec<&Lock>) =
+                locks.into_iter().partition(|l| l.owner.name == user);
+            reply(
+                200,
+                serde_json::json!({ "ours": ours, "theirs": theirs, "next_cursor": next_cursor }),
+            )
+        }
+        (tiny_http::Method::Post, ["locks", id, "unlock"]) => {
+            let unlock: UnlockBody = serde_json::from_str(&body).unwrap_or_default();
+            let index = match store.locks.iter().position(|l| l.id == *id) {
+                Some(i) => i,
+                None => return message(404, "lock not found"),
+            };
+            if store.locks[index].owner.name != user && !unlock.force {
+                return message(403, "lock is owned by another user");
+            }
+            let lock = store.locks.remove(index);
+            reply(200, serde_json::json!({ "lock": lock }))
+        }
+        _ => message(404, "not found"),
+    }
+}
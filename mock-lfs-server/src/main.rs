@@ -0,0 +1,37 @@
+use mock_lfs_server::{Config, MockServer};
+use std::time::Duration;
+
+const USAGE: &str = "usage: mock-lfs-server [--port <port>] [--user <name:password>]... \
+                     [--latency-ms <ms>] [--fail-every <n>]";
+
+fn main() {
+    let mut config = Config::default();
+    let mut port = 8080;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| panic!("{}", USAGE));
+        match arg.as_str() {
+            "--port" => port = value().parse().expect("invalid port"),
+            "--user" => {
+                let user = value();
+                let mut parts = user.splitn(2, ':');
+                let name = parts.next().unwrap().to_string();
+                let password = parts
+                    .next()
+                    .expect("user must be name:password")
+                    .to_string();
+                config.users.push((name, password));
+            }
+            "--latency-ms" => {
+                config.latency = Duration::from_millis(value().parse().expect("invalid latency"))
+            }
+            "--fail-every" => config.fail_every = Some(value().parse().expect("invalid count")),
+            _ => panic!("{}", USAGE),
+        }
+    }
+
+    let server = MockServer::start_on(config, port).expect("failed to start server");
+    println!("mock lfs server listening on {}", server.url());
+    println!("use it with: git config lfs.url {}", server.url());
+    server.wait();
+}
@@ -0,0 +1,153 @@
+//! Optional localhost http server, so editor plugins and pipeline scripts can
+//! query and take locks through the same backend as the gui.
+//!
+//! Every request is a `POST /api` with a `backend_api::Envelope<Request>` json body
+//! and an `Authorization: Bearer <token>` header, where the token is read from the
+//! file returned by [`token_path`]. Answers are enveloped the same way.
+
+use crate::handler::{self, State};
+use crate::settings;
+use anyhow::{anyhow, Context, Result};
+use backend_api::{Envelope, Request, Response};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::path::PathBuf;
+
+pub const DEFAULT_PORT: u16 = 47821;
+
+pub fn token_path() -> Result<PathBuf> {
+    Ok(settings::config_dir()?.join("api-token"))
+}
+
+/// Reads the api token, generating one on first use.
+fn load_or_create_token() -> Result<String> {
+    let path = token_path()?;
+    if let Ok(token) = std::fs::read_to_string(&path) {
+        let token = token.trim().to_string();
+        if !token.is_empty() {
+            return Ok(token);
+        }
+    }
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(&path, &token).with_context(|| format!("failed to write {:?}", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(token)
+}
+
+fn json_response(status: u16, body: String) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    tiny_http::Response::from_string(body)
+        .with_status_code(status)
+        .with_header(
+            tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap(),
+        )
+}
+
+fn error_response(status: u16, message: &str) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    json_response(status, serde_json::json!({ "error": message }).to_string())
+}
+
+fn is_authorized(request: &tiny_http::Request, token: &str) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .map(|h| h.value.as_str().trim() == format!("Bearer {}", token))
+        .unwrap_or(false)
+}
+
+/// Why `request` can't be answered over the api, for requests that need someone in
+/// front of the app.
+fn unavailable(request: &Request) -> Option<&'static str> {
+    match request {
+        Request::PickRepo => {
+            Some("pickRepo opens a folder dialog and isn't available over the api")
+        }
+        Request::ExportLocks { path: Some(_), .. } => Some(
+            "exportLocks doesn't write files over the api, leave out path to get the export in contents",
+        ),
+        _ => None,
+    }
+}
+
+/// Like `handler::dispatch`, but an export is answered with its contents instead of
+/// asking where to save it.
+fn dispatch(state: &State, envelope: Envelope<Request>) -> Result<Envelope<Response>> {
+    match envelope.payload {
+        Request::ExportLocks {
+            filter,
+            format,
+            path: None,
+        } => {
+            handler::check_version(envelope.version)?;
+            let (contents, count) = state.export_locks(&filter, format)?;
+            Ok(Envelope {
+                version: envelope.version,
+                id: envelope.id,
+                payload: Response::ExportLocks {
+                    path: None,
+                    count,
+                    contents: Some(contents),
+                },
+            })
+        }
+        _ => handler::dispatch(state, envelope),
+    }
+}
+
+fn respond(state: &State, token: &str, mut request: tiny_http::Request) {
+    let response = if *request.method() != tiny_http::Method::Post || request.url() != "/api" {
+        error_response(404, "only POST /api is supported")
+    } else if !is_authorized(&request, token) {
+        error_response(401, "missing or invalid token")
+    } else {
+        let mut body = String::new();
+        match request.as_reader().read_to_string(&mut body) {
+            Err(e) => error_response(400, &e.to_string()),
+            Ok(_) => match serde_json::from_str::<Envelope<Request>>(&body) {
+                Err(e) => error_response(400, &e.to_string()),
+                Ok(envelope) => {
+                    let id = envelope.id;
+                    match unavailable(&envelope.payload) {
+                        Some(reason) => json_response(
+                            400,
+                            serde_json::json!({ "id": id, "error": reason }).to_string(),
+                        ),
+                        None => match dispatch(state, envelope) {
+                            Ok(r) => json_response(200, serde_json::to_string(&r).unwrap()),
+                            Err(e) => json_response(
+                                500,
+                                serde_json::json!({ "id": id, "error": e.to_string() }).to_string(),
+                            ),
+                        },
+                    }
+                }
+            },
+        }
+    };
+    if let Err(e) = request.respond(response) {
+        println!("failed to send api response, {}", e);
+    }
+}
+
+/// Serves the api on `127.0.0.1:port` until the process exits.
+pub fn serve(state: State, port: u16) -> Result<()> {
+    let token = load_or_create_token()?;
+    let server = tiny_http::Server::http(("127.0.0.1", port))
+        .map_err(|e| anyhow!("failed to start api server on port {}, {}", port, e))?;
+    println!("api server listening on 127.0.0.1:{}", port);
+    for request in server.incoming_requests() {
+        let state = state.clone();
+        let token = token.clone();
+        std::thread::spawn(move || respond(&state, &token, request));
+    }
+    Ok(())
+}
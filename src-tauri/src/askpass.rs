@@ -0,0 +1,150 @@
+//! Git and ssh prompts answered in the app. Commands run without a terminal, so
+//! `GIT_ASKPASS` and `SSH_ASKPASS` point at our own executable, which then hands the
+//! prompt to the running app over a localhost socket and prints the user's answer.
+
+use crate::events::EventBus;
+use anyhow::{anyhow, Result};
+use backend_api::Event;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Tells our executable it runs as a helper, holds where to reach the app.
+pub const ENV: &str = "GIT_LOCK_MANAGER_ASKPASS";
+
+/// How long a prompt waits for the user before it counts as cancelled.
+pub const TIMEOUT: Duration = Duration::from_secs(300);
+
+/// What a helper sends, one json line.
+#[derive(Serialize, Deserialize)]
+struct Ask {
+    token: String,
+    prompt: String,
+}
+
+/// What the app answers, `None` if the prompt was cancelled.
+#[derive(Serialize, Deserialize)]
+struct Answer {
+    answer: Option<String>,
+}
+
+pub struct Askpass {
+    addr: SocketAddr,
+    /// Keeps other local processes from asking the user in our name.
+    token: String,
+    timeout: Duration,
+    events: Arc<EventBus>,
+    next_id: AtomicU32,
+    pending: Mutex<HashMap<u32, Sender<Option<String>>>>,
+}
+
+impl Askpass {
+    /// Listens for helpers on a free localhost port, prompts are shown through `events`.
+    pub fn start(events: Arc<EventBus>, timeout: Duration) -> Result<Arc<Self>> {
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        let askpass = Arc::new(Self {
+            addr: listener.local_addr()?,
+            token: rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(32)
+                .map(char::from)
+                .collect(),
+            timeout,
+            events,
+            next_id: AtomicU32::new(1),
+            pending: Mutex::new(HashMap::new()),
+        });
+        let server = askpass.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let askpass = server.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = askpass.serve(stream) {
+                        println!("askpass helper failed, {}", e);
+                    }
+                });
+            }
+        });
+        Ok(askpass)
+    }
+
+    /// Environment making git and ssh ask through `helper`, our own executable.
+    pub fn env(&self, helper: &Path) -> Vec<(String, String)> {
+        let helper = helper.to_string_lossy().to_string();
+        vec![
+            ("GIT_TERMINAL_PROMPT".to_string(), "0".to_string()),
+            ("GIT_ASKPASS".to_string(), helper.clone()),
+            ("SSH_ASKPASS".to_string(), helper),
+            // ssh only asks without a terminal and with a display otherwise
+            ("SSH_ASKPASS_REQUIRE".to_string(), "force".to_string()),
+            (ENV.to_string(), format!("{} {}", self.addr, self.token)),
+        ]
+    }
+
+    fn serve(&self, mut stream: TcpStream) -> Result<()> {
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        let ask: Ask = serde_json::from_str(&line)?;
+        if ask.token != self.token {
+            return Err(anyhow!("wrong token"));
+        }
+        let answer = self.ask(ask.prompt);
+        writeln!(stream, "{}", serde_json::to_string(&Answer { answer })?)?;
+        Ok(())
+    }
+
+    /// Shows `prompt` and waits for the answer, `None` if cancelled or timed out.
+    fn ask(&self, prompt: String) -> Option<String> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = mpsc::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        let lower = prompt.to_lowercase();
+        let secret = ["password", "passphrase", "pin", "token"]
+            .iter()
+            .any(|w| lower.contains(w));
+        self.events
+            .emit(Event::CredentialPrompt { id, prompt, secret });
+        let answer = receiver.recv_timeout(self.timeout).unwrap_or(None);
+        self.pending.lock().unwrap().remove(&id);
+        self.events.emit(Event::PromptClosed { id });
+        answer
+    }
+
+    /// Relays the user's `answer` to prompt `id`, `None` cancels it.
+    pub fn answer(&self, id: u32, answer: Option<String>) -> Result<()> {
+        let sender = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(&id)
+            .ok_or(anyhow!("prompt {} is already closed", id))?;
+        sender
+            .send(answer)
+            .map_err(|_| anyhow!("prompt {} is already closed", id))
+    }
+}
+
+/// Helper side, asks the app reachable through `connect`, the value of [`ENV`], to
+/// answer `prompt`.
+pub fn ask_app(connect: &str, prompt: &str) -> Result<Option<String>> {
+    let (addr, token) = connect
+        .split_once(' ')
+        .ok_or(anyhow!("malformed {}", ENV))?;
+    let mut stream = TcpStream::connect(addr)?;
+    let ask = Ask {
+        token: token.to_string(),
+        prompt: prompt.to_string(),
+    };
+    writeln!(stream, "{}", serde_json::to_string(&ask)?)?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str::<Answer>(&line)?.answer)
+}
@@ -0,0 +1,159 @@
+//! Credentials for the lfs api, asked from git's credential helpers like `git lfs`
+//! does, and a cache for the tokens `git-lfs-authenticate` hands out.
+
+use crate::runner::{CommandRunner, GitCommand};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// What `git credential` reads and writes, `key=value` lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Credential {
+    pub protocol: String,
+    /// Host with the port if there is one, like `example.com:8080`.
+    pub host: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Credential {
+    /// The credential to ask for when talking to `url`, with a user name it carries.
+    pub fn for_url(url: &str) -> Result<Self> {
+        let (protocol, rest) = url
+            .split_once("://")
+            .ok_or(anyhow!("{} is not an http url", url))?;
+        let authority = rest.split('/').next().unwrap_or_default();
+        let (username, host) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user.split(':').next().unwrap().to_string()), host),
+            None => (None, authority),
+        };
+        Ok(Self {
+            protocol: protocol.to_string(),
+            host: host.to_string(),
+            username,
+            password: None,
+        })
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut credential = Self::default();
+        for (key, value) in text.lines().filter_map(|l| l.split_once('=')) {
+            match key {
+                "protocol" => credential.protocol = value.to_string(),
+                "host" => credential.host = value.to_string(),
+                "username" => credential.username = Some(value.to_string()),
+                "password" => credential.password = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        credential
+    }
+
+    /// The input of `git credential`, ended by a blank line.
+    pub fn to_input(&self) -> String {
+        let mut input = format!("protocol={}\nhost={}\n", self.protocol, self.host);
+        if let Some(username) = &self.username {
+            input.push_str(&format!("username={}\n", username));
+        }
+        if let Some(password) = &self.password {
+            input.push_str(&format!("password={}\n", password));
+        }
+        input.push('\n');
+        input
+    }
+}
+
+fn credential(
+    runner: &dyn CommandRunner,
+    repo: &Path,
+    action: &str,
+    credential: &Credential,
+) -> Result<String> {
+    let command = GitCommand::new(repo)
+        .args(["credential", action])
+        .stdin(credential.to_input());
+    let output = runner
+        .run(&command)
+        .map_err(|e| anyhow!("failed to run git credential {}, {}", action, e))?;
+    match output.success {
+        true => Ok(output.stdout),
+        false => Err(anyhow!(
+            "git credential {} failed, {}",
+            action,
+            output.stderr.trim()
+        )),
+    }
+}
+
+/// Completes `partial` with a user name and password from the configured helpers.
+pub fn fill(runner: &dyn CommandRunner, repo: &Path, partial: &Credential) -> Result<Credential> {
+    let filled = Credential::parse(&credential(runner, repo, "fill", partial)?);
+    match filled.password {
+        Some(_) => Ok(filled),
+        None => Err(anyhow!("no password for {}", partial.host)),
+    }
+}
+
+/// Tells the helpers `filled` worked, so they can store it.
+pub fn approve(runner: &dyn CommandRunner, repo: &Path, filled: &Credential) -> Result<()> {
+    credential(runner, repo, "approve", filled).map(|_| ())
+}
+
+/// Tells the helpers `filled` was refused, so they forget it.
+pub fn reject(runner: &dyn CommandRunner, repo: &Path, filled: &Credential) -> Result<()> {
+    credential(runner, repo, "reject", filled).map(|_| ())
+}
+
+/// Where tokens are kept between api calls.
+pub trait TokenStore: Send + Sync {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&self, key: &str, token: &str);
+    fn remove(&self, key: &str);
+}
+
+/// Keeps tokens until the app exits.
+#[derive(Default)]
+pub struct MemoryStore {
+    tokens: Mutex<HashMap<String, String>>,
+}
+
+impl TokenStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<String> {
+        self.tokens.lock().unwrap().get(key).cloned()
+    }
+
+    fn set(&self, key: &str, token: &str) {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), token.to_string());
+    }
+
+    fn remove(&self, key: &str) {
+        self.tokens.lock().unwrap().remove(key);
+    }
+}
+
+/// Keeps tokens in the keychain, credential manager or secret service of the OS.
+#[cfg(feature = "secret-store")]
+pub struct SecretStore;
+
+#[cfg(feature = "secret-store")]
+impl TokenStore for SecretStore {
+    fn get(&self, key: &str) -> Option<String> {
+        keyring::Keyring::new("git-lock-manager", key)
+            .get_password()
+            .ok()
+    }
+
+    fn set(&self, key: &str, token: &str) {
+        if let Err(e) = keyring::Keyring::new("git-lock-manager", key).set_password(token) {
+            println!("failed to store token for {}, {}", key, e);
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = keyring::Keyring::new("git-lock-manager", key).delete_password();
+    }
+}
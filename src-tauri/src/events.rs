@@ -0,0 +1,43 @@
+//! Events the backend pushes without a request, fanned out to whoever subscribed,
+//! e.g. the webview.
+
+use backend_api::{Event, LockEntry};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Receives every emitted event.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &Event);
+}
+
+#[derive(Default)]
+pub struct EventBus {
+    sinks: Mutex<Vec<Arc<dyn EventSink>>>,
+}
+
+impl EventBus {
+    pub fn subscribe(&self, sink: Arc<dyn EventSink>) {
+        self.sinks.lock().unwrap().push(sink);
+    }
+
+    pub fn emit(&self, event: Event) {
+        for sink in self.sinks.lock().unwrap().iter() {
+            sink.emit(&event);
+        }
+    }
+}
+
+/// Locks added and removed between two lock lists, a lock whose id changed is both.
+pub fn lock_changes(before: &[LockEntry], after: &[LockEntry]) -> Vec<Event> {
+    let before_ids: HashMap<&str, &LockEntry> = before.iter().map(|l| (l.id.as_str(), l)).collect();
+    let after_ids: HashMap<&str, &LockEntry> = after.iter().map(|l| (l.id.as_str(), l)).collect();
+    let removed = before
+        .iter()
+        .filter(|l| !after_ids.contains_key(l.id.as_str()))
+        .map(|l| Event::LockRemoved { lock: l.clone() });
+    let added = after
+        .iter()
+        .filter(|l| !before_ids.contains_key(l.id.as_str()))
+        .map(|l| Event::LockAdded { lock: l.clone() });
+    removed.chain(added).collect()
+}
@@ -0,0 +1,148 @@
+//! Lock reports for people outside the app, e.g. a weekly "what is locked and by
+//! whom" for producers.
+
+use anyhow::Result;
+use backend_api::{ExportFormat, LockEntry};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// One exported lock.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LockReport {
+    pub path: String,
+    pub owner: String,
+    pub locked_at: String,
+    /// Time since the lock was taken like `3d 4h`, empty if `locked_at` can't be read.
+    pub age: String,
+    /// `modified` if our working copy has uncommitted changes to the file, else `clean`.
+    pub status: String,
+}
+
+const COLUMNS: [&str; 5] = ["Path", "Owner", "Locked At", "Age", "Status"];
+
+impl LockReport {
+    fn fields(&self) -> [&str; 5] {
+        [
+            &self.path,
+            &self.owner,
+            &self.locked_at,
+            &self.age,
+            &self.status,
+        ]
+    }
+}
+
+/// Time between `locked_at` and `now` in its two largest units.
+pub fn age(locked_at: &str, now: DateTime<Utc>) -> String {
+    match DateTime::parse_from_rfc3339(locked_at) {
+        Ok(t) => format_duration((now - t.with_timezone(&Utc)).num_seconds()),
+        Err(_) => String::new(),
+    }
+}
+
+/// `seconds` in its two largest units, like `3d 4h` or `25m`.
+pub fn format_duration(seconds: i64) -> String {
+    let minutes = seconds.max(0) / 60;
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+pub fn reports(
+    locks: &[LockEntry],
+    modified: &HashSet<String>,
+    now: DateTime<Utc>,
+) -> Vec<LockReport> {
+    locks
+        .iter()
+        .map(|l| LockReport {
+            path: l.path.clone(),
+            owner: l.owner.name.clone(),
+            locked_at: l.locked_at.clone(),
+            age: age(&l.locked_at, now),
+            status: match modified.contains(&l.path) {
+                true => "modified".to_string(),
+                false => "clean".to_string(),
+            },
+        })
+        .collect()
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains(&[',', '"', '\n', '\r'][..]) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+fn csv(reports: &[LockReport]) -> String {
+    let line = |fields: &[&str]| {
+        let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        format!("{}\r\n", fields.join(","))
+    };
+    let mut out = line(&COLUMNS);
+    reports
+        .iter()
+        .for_each(|r| out.push_str(&line(&r.fields())));
+    out
+}
+
+fn markdown(title: &str, reports: &[LockReport]) -> String {
+    let line = |fields: &[&str]| {
+        let fields: Vec<String> = fields.iter().map(|f| f.replace('|', "\\|")).collect();
+        format!("| {} |\n", fields.join(" | "))
+    };
+    let mut out = format!("# {}\n\n", title);
+    out.push_str(&line(&COLUMNS));
+    out.push_str(&line(&["---"; 5]));
+    reports
+        .iter()
+        .for_each(|r| out.push_str(&line(&r.fields())));
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html(title: &str, reports: &[LockReport]) -> String {
+    let row = |cell: &str, fields: &[&str]| {
+        let cells: String = fields
+            .iter()
+            .map(|f| format!("<{0}>{1}</{0}>", cell, escape_html(f)))
+            .collect();
+        format!("<tr>{}</tr>\n", cells)
+    };
+    let rows: String = reports.iter().map(|r| row("td", &r.fields())).collect();
+    format!(
+        "<!DOCTYPE html>\n\
+         <html><head><meta charset=\"utf-8\"><title>{0}</title>\n\
+         <style>body{{font-family: sans-serif; margin: 2em}}\
+         table{{border-collapse: collapse}}\
+         th, td{{border: 1px solid #cbcbcb; padding: .4em .8em; text-align: left}}\
+         th{{background-color: #e0e0e0}}\
+         tr:nth-child(2n) td{{background-color: #f2f2f2}}</style>\n\
+         </head><body>\n<h1>{0}</h1>\n<table>\n<thead>{1}</thead>\n<tbody>\n{2}</tbody>\n</table>\n\
+         </body></html>\n",
+        escape_html(title),
+        row("th", &COLUMNS).trim_end(),
+        rows
+    )
+}
+
+/// The file content of `reports` in `format`, `title` heads the formats that have one.
+pub fn render(format: ExportFormat, title: &str, reports: &[LockReport]) -> Result<String> {
+    Ok(match format {
+        ExportFormat::Csv => csv(reports),
+        ExportFormat::Json => serde_json::to_string_pretty(reports)?,
+        ExportFormat::Markdown => markdown(title, reports),
+        ExportFormat::Html => html(title, reports),
+    })
+}
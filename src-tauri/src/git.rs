@@ -0,0 +1,242 @@
+use crate::runner::{CommandOutput, CommandRunner, GitCommand};
+use anyhow::{anyhow, Result};
+use backend_api as api;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+fn run(runner: &dyn CommandRunner, command: GitCommand) -> Result<CommandOutput> {
+    let output = runner
+        .run(&command)
+        .map_err(|e| anyhow!("failed to run git {}, {}", command.args.join(" "), e))?;
+    match output.success {
+        true => Ok(output),
+        false => Err(anyhow!(
+            "git {} failed, {}",
+            command.args.join(" "),
+            output.stderr.trim()
+        )),
+    }
+}
+
+pub fn get_lfs_files(runner: &dyn CommandRunner, path: &Path) -> Result<Vec<String>> {
+    let output = run(
+        runner,
+        GitCommand::new(path).args(["lfs", "ls-files", "-n"]),
+    )?;
+    Ok(output.stdout.lines().map(|s| String::from(s)).collect())
+}
+
+/// Every path in the index, lfs or not.
+pub fn get_tracked_files(runner: &dyn CommandRunner, path: &Path) -> Result<Vec<String>> {
+    let output = run(runner, GitCommand::new(path).args(["ls-files", "-z"]))?;
+    Ok(output
+        .stdout
+        .split('\0')
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// The subset of `files` with the `lockable` attribute set.
+pub fn get_lockable_files(
+    runner: &dyn CommandRunner,
+    path: &Path,
+    files: &[String],
+) -> Result<HashSet<String>> {
+    let mut input = files.join("\0");
+    input.push('\0');
+    let output = run(
+        runner,
+        GitCommand::new(path)
+            .args(["check-attr", "-z", "--stdin", "lockable"])
+            .stdin(input),
+    )?;
+    // records are `<path>\0<attribute>\0<value>\0`
+    let fields: Vec<&str> = output.stdout.split('\0').collect();
+    Ok(fields
+        .chunks(3)
+        .filter(|r| r.len() == 3 && r[2] == "set")
+        .map(|r| r[0].to_string())
+        .collect())
+}
+
+pub fn get_locked_files(runner: &dyn CommandRunner, path: &Path) -> Result<Vec<api::LockEntry>> {
+    println!("getting files");
+    let output = run(
+        runner,
+        GitCommand::new(path).args(["lfs", "locks", "--json"]),
+    )?;
+    serde_json::from_str(output.stdout.as_str())
+        .map_err(|e| anyhow!("failed to parse locks, {}", e))
+}
+
+/// Our own locks as git lfs remembers them locally, without asking the server.
+pub fn get_local_locks(runner: &dyn CommandRunner, path: &Path) -> Result<Vec<api::LockEntry>> {
+    let output = run(
+        runner,
+        GitCommand::new(path).args(["lfs", "locks", "--local", "--json"]),
+    )?;
+    serde_json::from_str(output.stdout.as_str())
+        .map_err(|e| anyhow!("failed to parse local locks, {}", e))
+}
+
+#[derive(Deserialize)]
+struct VerifiedLocks {
+    ours: Vec<api::LockEntry>,
+}
+
+/// Ids of the locks owned by the user the lfs server authenticates us as.
+pub fn get_own_lock_ids(runner: &dyn CommandRunner, path: &Path) -> Result<HashSet<String>> {
+    let output = run(
+        runner,
+        GitCommand::new(path).args(["lfs", "locks", "--verify", "--json"]),
+    )?;
+    let locks: VerifiedLocks = serde_json::from_str(output.stdout.as_str())
+        .map_err(|e| anyhow!("failed to parse verified locks, {}", e))?;
+    Ok(locks.ours.into_iter().map(|l| l.id).collect())
+}
+
+/// Tracked files with staged or unstaged changes.
+pub fn get_modified_files(runner: &dyn CommandRunner, path: &Path) -> Result<HashSet<String>> {
+    let output = run(
+        runner,
+        GitCommand::new(path).args(["status", "--porcelain", "-z", "--untracked-files=no"]),
+    )?;
+    // entries are `XY <path>\0`, renames and copies are followed by the old path
+    let mut modified = HashSet::new();
+    let mut entries = output.stdout.split('\0').filter(|s| !s.is_empty());
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (status, file) = entry.split_at(3);
+        modified.insert(file.to_string());
+        if status.contains('R') || status.contains('C') {
+            entries.next();
+        }
+    }
+    Ok(modified)
+}
+
+pub fn lock_file(runner: &dyn CommandRunner, repo: &Path, file: &str) -> Result<api::LockEntry> {
+    println!("locking {}", file);
+    let output = run(
+        runner,
+        GitCommand::new(repo).args(["lfs", "lock", file, "--json"]),
+    )?;
+    // depending on the git-lfs version `--json` prints a single lock or an array
+    if let Ok(e) = serde_json::from_str::<api::LockEntry>(output.stdout.as_str()) {
+        return Ok(e);
+    }
+    match serde_json::from_str::<Vec<api::LockEntry>>(output.stdout.as_str()) {
+        Ok(mut e) if !e.is_empty() => Ok(e.remove(0)),
+        Ok(_) => Err(anyhow!("no lock returned for {}", file)),
+        Err(e) => Err(anyhow!("failed to parse lock of {}, {}", file, e)),
+    }
+}
+
+pub fn unlock_file(runner: &dyn CommandRunner, repo: &Path, id: u32) -> Result<()> {
+    println!("unlocking {}", id);
+    run(
+        runner,
+        GitCommand::new(repo).args(["lfs", "unlock", "-i", &id.to_string()]),
+    )?;
+    Ok(())
+}
+
+/// The branch the current one pushes to, `None` without one.
+pub fn get_upstream(runner: &dyn CommandRunner, path: &Path) -> Result<Option<String>> {
+    let command = GitCommand::new(path).args([
+        "rev-parse",
+        "--abbrev-ref",
+        "--symbolic-full-name",
+        "@{upstream}",
+    ]);
+    let output = runner
+        .run(&command)
+        .map_err(|e| anyhow!("failed to run git {}, {}", command.args.join(" "), e))?;
+    Ok(Some(output.stdout.trim().to_string()).filter(|_| output.success))
+}
+
+/// The committer email of `path`, `None` if it isn't configured.
+pub fn get_user_email(runner: &dyn CommandRunner, path: &Path) -> Result<Option<String>> {
+    let command = GitCommand::new(path).args(["config", "--get", "user.email"]);
+    let output = runner
+        .run(&command)
+        .map_err(|e| anyhow!("failed to run git {}, {}", command.args.join(" "), e))?;
+    Ok(Some(output.stdout.trim().to_string()).filter(|e| output.success && !e.is_empty()))
+}
+
+/// Files changed on the upstream branch by commits of `committer` since `since`, with
+/// the committer date of the newest such commit.
+pub fn get_pushed_changes(
+    runner: &dyn CommandRunner,
+    path: &Path,
+    since: &str,
+    committer: &str,
+) -> Result<HashMap<String, String>> {
+    let since = format!("--since={}", since);
+    let output = run(
+        runner,
+        GitCommand::new(path).args([
+            "log",
+            "--format=%x01%cI %ce",
+            "--name-only",
+            "-z",
+            since.as_str(),
+            "@{upstream}",
+        ]),
+    )?;
+    // newest commit first, each is `\x01<date> <email>\0` followed by
+    // `\n<path>\0<path>\0...`
+    let mut changes = HashMap::new();
+    let mut date = None;
+    for entry in output
+        .stdout
+        .split('\0')
+        .map(|e| e.trim_start_matches('\n'))
+    {
+        match entry.strip_prefix('\u{1}') {
+            Some(commit) => {
+                let mut parts = commit.splitn(2, ' ');
+                let d = parts.next().unwrap_or("");
+                let email = parts.next().unwrap_or("");
+                date = Some(d).filter(|_| email.eq_ignore_ascii_case(committer));
+            }
+            None => {
+                if let Some(date) = date.filter(|_| !entry.is_empty()) {
+                    changes
+                        .entry(entry.to_string())
+                        .or_insert_with(|| date.to_string());
+                }
+            }
+        }
+    }
+    Ok(changes)
+}
+
+/// Files changed by commits that are not on the upstream branch yet.
+pub fn get_unpushed_files(runner: &dyn CommandRunner, path: &Path) -> Result<HashSet<String>> {
+    let output = run(
+        runner,
+        GitCommand::new(path).args(["log", "--format=", "--name-only", "-z", "@{upstream}..HEAD"]),
+    )?;
+    Ok(output
+        .stdout
+        .split('\0')
+        .map(|e| e.trim_start_matches('\n'))
+        .filter(|e| !e.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Path of `name` in the repo's hooks directory, honoring `core.hooksPath`.
+pub fn get_hook_path(runner: &dyn CommandRunner, path: &Path, name: &str) -> Result<PathBuf> {
+    let hook = format!("hooks/{}", name);
+    let output = run(
+        runner,
+        GitCommand::new(path).args(["rev-parse", "--git-path", hook.as_str()]),
+    )?;
+    Ok(path.join(output.stdout.trim()))
+}
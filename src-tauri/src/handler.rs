@@ -0,0 +1,985 @@
+use crate::askpass::Askpass;
+use crate::events::{self, EventBus};
+use crate::export;
+use crate::git;
+use crate::lfs_api::LfsClient;
+use crate::network;
+use crate::offline_queue::OfflineQueue;
+use crate::query::{Context, Query};
+use crate::quick_access::QuickAccess;
+use crate::release::{self, ReleaseMode};
+use crate::runner::CommandRunner;
+use crate::search::{self, FileIndex};
+use crate::snapshot::Snapshots;
+use crate::stats;
+use crate::tree;
+use crate::watch::{self, Notifier, Watcher};
+use anyhow::{anyhow, Result};
+use backend_api as api;
+use backend_api::{Envelope, Request, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use nfd2::Response;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Backend state shared by the webview and the local api server.
+#[derive(Clone)]
+pub struct State {
+    pub repo: Arc<RwLock<String>>,
+    pub lfs_files: Arc<Mutex<Vec<String>>>,
+    /// Built by the first search, dropped when another repo is picked.
+    pub file_index: Arc<Mutex<Option<Arc<FileIndex>>>>,
+    /// Locks from the last `GetLockedFiles`, sorted by path.
+    pub locks: Arc<Mutex<Vec<api::LockEntry>>>,
+    /// Ids of our own locks, verified with the server when a query asks for them.
+    pub own_locks: Arc<Mutex<Option<HashSet<String>>>>,
+    /// Pinned and recent files, kept in memory only unless replaced by a loaded one.
+    pub quick_access: Arc<Mutex<QuickAccess>>,
+    /// Last lock list fetched per repo, kept in memory only unless replaced by a loaded one.
+    pub snapshots: Arc<Mutex<Snapshots>>,
+    /// Set while the lfs server can't be reached and `locks` are the last known.
+    pub offline: Arc<Mutex<Option<api::Offline>>>,
+    /// Locks and unlocks waiting for the server, kept in memory only unless replaced by
+    /// a loaded one.
+    pub offline_queue: Arc<Mutex<OfflineQueue>>,
+    pub watcher: Arc<Mutex<Watcher>>,
+    /// Changes to watched files, oldest first.
+    pub inbox: Arc<Mutex<Vec<api::WatchEvent>>>,
+    /// Also shows watch events outside of the app when set.
+    pub notifier: Option<Arc<dyn Notifier>>,
+    /// Pushes lock changes, progress and background errors to subscribers.
+    pub events: Arc<EventBus>,
+    /// What to do with our locks once their changes are pushed.
+    pub release_mode: ReleaseMode,
+    /// Key bindings from the settings, replacing the defaults.
+    pub keys: HashMap<api::Command, String>,
+    /// Locale of the interface from the settings, empty for the system's.
+    pub locale: String,
+    /// Age in days from which the statistics list locks as stale.
+    pub stale_days: u32,
+    /// Relays git and ssh prompts to the user, `None` where nobody could answer.
+    pub askpass: Option<Arc<Askpass>>,
+    /// Talks to the locking api directly instead of through `git lfs` when set.
+    pub lfs_api: Option<Arc<LfsClient>>,
+    pub matcher: Arc<Mutex<SkimMatcherV2>>,
+    pub runner: Arc<dyn CommandRunner>,
+}
+
+impl State {
+    pub fn new(repo: String, runner: Arc<dyn CommandRunner>) -> Self {
+        let lfs_files = match repo.is_empty() {
+            true => Vec::new(),
+            false => git::get_lfs_files(&*runner, Path::new(&repo)).unwrap_or_else(|e| {
+                println!("{}", e);
+                Vec::new()
+            }),
+        };
+        Self {
+            repo: Arc::new(RwLock::new(repo)),
+            lfs_files: Arc::new(Mutex::new(lfs_files)),
+            file_index: Arc::new(Mutex::new(None)),
+            locks: Arc::new(Mutex::new(Vec::new())),
+            own_locks: Arc::new(Mutex::new(None)),
+            quick_access: Arc::new(Mutex::new(QuickAccess::default())),
+            snapshots: Arc::new(Mutex::new(Snapshots::default())),
+            offline: Arc::new(Mutex::new(None)),
+            offline_queue: Arc::new(Mutex::new(OfflineQueue::default())),
+            watcher: Arc::new(Mutex::new(Watcher::default())),
+            inbox: Arc::new(Mutex::new(Vec::new())),
+            notifier: None,
+            events: Arc::new(EventBus::default()),
+            release_mode: ReleaseMode::default(),
+            keys: HashMap::new(),
+            locale: String::new(),
+            stale_days: stats::StatsSettings::default().stale_days,
+            askpass: None,
+            lfs_api: None,
+            matcher: Arc::new(Mutex::new(SkimMatcherV2::default().ignore_case())),
+            runner,
+        }
+    }
+
+    fn repo_path(&self) -> PathBuf {
+        PathBuf::from(&*self.repo.read().unwrap())
+    }
+
+    fn get_locked_files(&self) -> Result<Vec<api::LockEntry>> {
+        match &self.lfs_api {
+            Some(api) => api.get_locked_files(&*self.runner, &self.repo_path()),
+            None => git::get_locked_files(&*self.runner, &self.repo_path()),
+        }
+    }
+
+    fn get_own_lock_ids(&self) -> Result<HashSet<String>> {
+        match &self.lfs_api {
+            Some(api) => api.get_own_lock_ids(&*self.runner, &self.repo_path()),
+            None => git::get_own_lock_ids(&*self.runner, &self.repo_path()),
+        }
+    }
+
+    fn lock_file(&self, file: &str) -> Result<api::LockEntry> {
+        match &self.lfs_api {
+            Some(api) => api.lock_file(&*self.runner, &self.repo_path(), file),
+            None => git::lock_file(&*self.runner, &self.repo_path(), file),
+        }
+    }
+
+    fn unlock_file(&self, id: u32) -> Result<()> {
+        match &self.lfs_api {
+            Some(api) => api.unlock_file(&*self.runner, &self.repo_path(), id),
+            None => git::unlock_file(&*self.runner, &self.repo_path(), id),
+        }
+    }
+
+    fn file_index(&self) -> Result<Arc<FileIndex>> {
+        let mut index = self.file_index.lock().unwrap();
+        if index.is_none() {
+            let lfs_files = self.lfs_files.lock().unwrap();
+            let built = FileIndex::build(&*self.runner, &self.repo_path(), &lfs_files)?;
+            self.events.emit(api::Event::CacheRebuilt {
+                files: built.tracked.len(),
+            });
+            *index = Some(Arc::new(built));
+        }
+        Ok(index.clone().unwrap())
+    }
+
+    fn own_locks(&self) -> Result<HashSet<String>> {
+        let mut own_locks = self.own_locks.lock().unwrap();
+        if own_locks.is_none() {
+            *own_locks = Some(self.get_own_lock_ids()?);
+        }
+        Ok(own_locks.clone().unwrap())
+    }
+
+    /// The locks matching the search box `filter`, in the order the search lists them.
+    pub fn filtered_locks(
+        &self,
+        filter: &str,
+        modified: &HashSet<String>,
+    ) -> Result<Vec<api::LockEntry>> {
+        let query = Query::parse(filter)?;
+        let own_locks = match query.needs_own_locks() {
+            true => Some(self.own_locks()?),
+            false => None,
+        };
+        if self.locks.lock().unwrap().is_empty() {
+            self.refresh_locks()?;
+        }
+        let locks = self.locks.lock().unwrap();
+        let paths: Vec<String> = locks.iter().map(|l| l.path.clone()).collect();
+        let results = search::rank(&self.matcher.lock().unwrap(), &paths, &query.text);
+        let context = Context {
+            locks: locks
+                .iter()
+                .map(|l| (l.path.as_str(), l))
+                .collect::<HashMap<_, _>>(),
+            own_locks: own_locks.as_ref(),
+            modified: Some(modified),
+            now: chrono::Utc::now(),
+        };
+        Ok(results
+            .iter()
+            .filter(|r| query.matches(&r.path, &context))
+            .filter_map(|r| context.locks.get(r.path.as_str()).map(|&l| l.clone()))
+            .collect())
+    }
+
+    /// The locks matching `filter` rendered as `format`, and how many there are.
+    pub fn export_locks(&self, filter: &str, format: api::ExportFormat) -> Result<(String, usize)> {
+        let repo = self.repo_path();
+        let modified = git::get_modified_files(&*self.runner, &repo)?;
+        let locks = self.filtered_locks(filter, &modified)?;
+        let now = chrono::Utc::now();
+        let reports = export::reports(&locks, &modified, now);
+        let title = format!(
+            "Locks of {} on {}",
+            repo.file_name().unwrap_or_default().to_string_lossy(),
+            now.format("%Y-%m-%d %H:%M UTC")
+        );
+        Ok((export::render(format, &title, &reports)?, reports.len()))
+    }
+
+    /// Statistics of the current locks, fetched first if none were yet.
+    pub fn lock_stats(&self, stale_days: u32) -> Result<api::LockStats> {
+        if self.locks.lock().unwrap().is_empty() {
+            self.refresh_locks()?;
+        }
+        let locks = self.locks.lock().unwrap();
+        Ok(stats::compute(&locks, chrono::Utc::now(), stale_days))
+    }
+
+    /// Switches to the repository at `path`, forgetting everything known about the
+    /// previous one, and asks its server for locks to find out if it can be reached.
+    pub fn open_repo(&self, path: &Path) -> Result<()> {
+        let lfs_files = git::get_lfs_files(&*self.runner, path)?;
+        *self.repo.write().unwrap() = String::from(path.to_str().unwrap());
+        *self.lfs_files.lock().unwrap() = lfs_files;
+        *self.file_index.lock().unwrap() = None;
+        self.locks.lock().unwrap().clear();
+        *self.own_locks.lock().unwrap() = None;
+        self.watcher.lock().unwrap().reset();
+        self.set_offline(None);
+        let repo = self.repo.read().unwrap().clone();
+        self.events.emit(api::Event::RepoChanged { repo });
+        if let Err(e) = self.refresh_locks() {
+            println!("failed to get the locks of the new repository, {}", e);
+        }
+        Ok(())
+    }
+
+    /// Asks the server for the current locks, replays what was asked for while offline,
+    /// locks queued files that are free and reports changes to watched files. If the
+    /// server can't be reached the last known locks are listed instead.
+    pub fn refresh_locks(&self) -> Result<()> {
+        let repo = self.repo.read().unwrap().clone();
+        let mut locks = match self.get_locked_files() {
+            Ok(locks) => locks,
+            Err(e) if network::is_unreachable(&e) => return self.go_offline(&repo, e),
+            Err(e) => return Err(e),
+        };
+        self.replay_pending(&repo, &mut locks);
+        let files = self.quick_access.lock().unwrap().files(&repo);
+        let acquired = self.acquire_queued(&repo, &files.queued, &mut locks);
+        locks.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut events = self.watcher.lock().unwrap().update(&files.watched, &locks);
+        // our own queued lock is reported once, as acquired
+        events.retain(|e| !acquired.iter().any(|a| a.path == e.path));
+        events.extend(acquired);
+        let changes = {
+            let mut current = self.locks.lock().unwrap();
+            let changes = events::lock_changes(&current, &locks);
+            *current = locks;
+            changes
+        };
+        changes.into_iter().for_each(|e| self.events.emit(e));
+        *self.own_locks.lock().unwrap() = None;
+        self.report(events);
+        let locks = self.locks.lock().unwrap().clone();
+        if let Err(e) = self.snapshots.lock().unwrap().set(&repo, &locks) {
+            println!("failed to save the lock snapshot, {}", e);
+        }
+        self.set_offline(None);
+        Ok(())
+    }
+
+    /// Lists the last snapshot of `repo` and our own locks git lfs knows locally,
+    /// failing with `error` if there are neither.
+    fn go_offline(&self, repo: &str, error: anyhow::Error) -> Result<()> {
+        let snapshot = self.snapshots.lock().unwrap().get(repo);
+        let local = git::get_local_locks(&*self.runner, &self.repo_path()).unwrap_or_else(|e| {
+            println!("{}", e);
+            Vec::new()
+        });
+        if snapshot.is_none() && local.is_empty() {
+            return Err(error);
+        }
+        let (since, mut locks) = match snapshot {
+            Some(snapshot) => (Some(snapshot.time), snapshot.locks),
+            None => (None, Vec::new()),
+        };
+        for lock in local {
+            if !locks.iter().any(|l| l.path == lock.path) {
+                locks.push(lock);
+            }
+        }
+        locks.sort_by(|a, b| a.path.cmp(&b.path));
+        let changes = {
+            let mut current = self.locks.lock().unwrap();
+            let changes = events::lock_changes(&current, &locks);
+            *current = locks;
+            changes
+        };
+        changes.into_iter().for_each(|e| self.events.emit(e));
+        self.set_offline(Some(api::Offline {
+            since,
+            error: error.to_string(),
+        }));
+        Ok(())
+    }
+
+    /// Runs `run`, or keeps `operation` to be replayed later if the server can't be
+    /// reached. `None` if it was kept.
+    fn or_pending<T>(
+        &self,
+        operation: api::PendingOperation,
+        run: impl FnOnce() -> Result<T>,
+    ) -> Result<Option<T>> {
+        if self.offline.lock().unwrap().is_none() {
+            match run() {
+                Ok(done) => return Ok(Some(done)),
+                Err(e) if network::is_unreachable(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        println!(
+            "server unreachable, keeping the operation on {}",
+            operation.path
+        );
+        let repo = self.repo.read().unwrap().clone();
+        self.offline_queue.lock().unwrap().push(&repo, operation)?;
+        Ok(None)
+    }
+
+    /// Does the locks and unlocks kept while offline in order, on top of the fetched
+    /// `locks`, and reports how they went. Stops when the server is lost again,
+    /// keeping the rest.
+    fn replay_pending(&self, repo: &str, locks: &mut Vec<api::LockEntry>) {
+        let pending = self.offline_queue.lock().unwrap().pending(repo);
+        if pending.is_empty() {
+            return;
+        }
+        let mut result = api::BatchResult::default();
+        let mut own_locks = None;
+        for operation in pending {
+            match self.replay(&operation, locks, &mut own_locks) {
+                Ok(()) => result.done.push(operation.path.clone()),
+                Err(e) if network::is_unreachable(&e) => {
+                    println!("server lost while replaying {}, {}", operation.path, e);
+                    break;
+                }
+                Err(e) => result.failed.push(api::BatchFailure {
+                    path: operation.path.clone(),
+                    error: e.to_string(),
+                }),
+            }
+            self.touch(&operation.path);
+            if let Err(e) = self.offline_queue.lock().unwrap().remove(repo, &operation) {
+                println!("failed to save the offline queue, {}", e);
+            }
+        }
+        if !result.done.is_empty() || !result.failed.is_empty() {
+            self.events.emit(api::Event::PendingReplayed { result });
+        }
+    }
+
+    /// Does one pending `operation`, failing if someone else holds the file by now.
+    fn replay(
+        &self,
+        operation: &api::PendingOperation,
+        locks: &mut Vec<api::LockEntry>,
+        own_locks: &mut Option<HashSet<String>>,
+    ) -> Result<()> {
+        let held = locks.iter().position(|l| l.path == operation.path);
+        match (operation.lock, held) {
+            (true, None) => {
+                locks.push(self.lock_file(&operation.path)?);
+                Ok(())
+            }
+            (true, Some(at)) => {
+                if own_locks.is_none() {
+                    *own_locks = Some(self.get_own_lock_ids()?);
+                }
+                match own_locks.as_ref().unwrap().contains(&locks[at].id) {
+                    true => Ok(()),
+                    false => Err(anyhow!("already locked by {}", locks[at].owner.name)),
+                }
+            }
+            // released meanwhile, nothing left to do
+            (false, None) => Ok(()),
+            (false, Some(at)) => {
+                if operation.id.as_ref() != Some(&locks[at].id) {
+                    return Err(anyhow!("locked again by {}", locks[at].owner.name));
+                }
+                self.unlock_file(locks[at].id.parse()?)?;
+                locks.remove(at);
+                Ok(())
+            }
+        }
+    }
+
+    /// Updates whether the server can be reached, pushing the change if there is one.
+    fn set_offline(&self, offline: Option<api::Offline>) {
+        let mut current = self.offline.lock().unwrap();
+        if current.is_some() != offline.is_some() {
+            self.events.emit(api::Event::ConnectionChanged {
+                offline: offline.clone(),
+            });
+        }
+        *current = offline;
+    }
+
+    /// Notifies `events` and adds them to the inbox.
+    fn report(&self, events: Vec<api::WatchEvent>) {
+        if events.is_empty() {
+            return;
+        }
+        if let Some(notifier) = &self.notifier {
+            events.iter().for_each(|e| notifier.notify(e));
+        }
+        let mut inbox = self.inbox.lock().unwrap();
+        inbox.extend(events);
+        let overflow = inbox.len().saturating_sub(watch::INBOX_LIMIT);
+        inbox.drain(..overflow);
+    }
+
+    /// Finds our locks whose changes are pushed. With `ReleaseMode::Auto` they are
+    /// unlocked and reported, otherwise only returned.
+    pub fn release_pushed(
+        &self,
+        mode: ReleaseMode,
+    ) -> Result<(Vec<api::LockEntry>, api::BatchResult)> {
+        if mode == ReleaseMode::Off {
+            return Ok((Vec::new(), api::BatchResult::default()));
+        }
+        self.refresh_locks()?;
+        let own_locks = self.own_locks()?;
+        let mine: Vec<api::LockEntry> = self
+            .locks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|l| own_locks.contains(&l.id))
+            .cloned()
+            .collect();
+        let pushed = release::pushed_locks(&*self.runner, &self.repo_path(), &mine)?;
+        if mode == ReleaseMode::Prompt || pushed.is_empty() {
+            return Ok((pushed, api::BatchResult::default()));
+        }
+        let result = self.unlock_all("Unlock pushed files", pushed.clone());
+        let time = chrono::Utc::now().to_rfc3339();
+        self.report(
+            pushed
+                .into_iter()
+                .filter(|l| result.done.contains(&l.path))
+                .map(|l| api::WatchEvent {
+                    path: l.path,
+                    owner: l.owner.name,
+                    locked: false,
+                    automatic: true,
+                    time: time.clone(),
+                })
+                .collect(),
+        );
+        self.refresh_locks()?;
+        Ok((Vec::new(), result))
+    }
+
+    /// Locks the `queued` files missing from `locks` and adds the new locks. A file
+    /// someone else got first stays queued for the next refresh.
+    fn acquire_queued(
+        &self,
+        repo: &str,
+        queued: &[String],
+        locks: &mut Vec<api::LockEntry>,
+    ) -> Vec<api::WatchEvent> {
+        let mut events = Vec::new();
+        for path in queued {
+            if locks.iter().any(|l| &l.path == path) {
+                continue;
+            }
+            let lock = match self.lock_file(path) {
+                Ok(lock) => lock,
+                Err(e) => {
+                    println!("failed to lock queued {}, {}", path, e);
+                    continue;
+                }
+            };
+            if let Err(e) = self.quick_access.lock().unwrap().queue(repo, path, false) {
+                println!("failed to save queued files, {}", e);
+            }
+            self.touch(path);
+            events.push(api::WatchEvent {
+                path: path.clone(),
+                owner: lock.owner.name.clone(),
+                locked: true,
+                automatic: true,
+                time: chrono::Utc::now().to_rfc3339(),
+            });
+            locks.push(lock);
+        }
+        events
+    }
+
+    fn progress(&self, job: &str, result: &api::BatchResult, total: usize) {
+        self.events.emit(api::Event::JobProgress {
+            job: job.to_string(),
+            done: result.done.len() + result.failed.len(),
+            total,
+        });
+    }
+
+    /// Unlocks `locks` one by one, carrying on after failures, `job` names the
+    /// progress events.
+    fn unlock_all(&self, job: &str, locks: Vec<api::LockEntry>) -> api::BatchResult {
+        let mut result = api::BatchResult::default();
+        let total = locks.len();
+        for lock in locks {
+            let unlocked = lock
+                .id
+                .parse()
+                .map_err(|_| anyhow!("invalid lock id {}", lock.id))
+                .and_then(|id| self.unlock_file(id));
+            match unlocked {
+                Ok(_) => result.done.push(lock.path),
+                Err(e) => result.failed.push(api::BatchFailure {
+                    path: lock.path,
+                    error: e.to_string(),
+                }),
+            }
+            self.progress(job, &result, total);
+        }
+        result
+    }
+
+    /// Remembers `file` as recently used, a failure to save is only logged.
+    fn touch(&self, file: &str) {
+        let repo = self.repo.read().unwrap().clone();
+        if let Err(e) = self.quick_access.lock().unwrap().touch(&repo, file) {
+            println!("failed to save recent files, {}", e);
+        }
+    }
+
+    /// `files` tagged like search results, with their current locks.
+    fn quick_access_entries(&self, files: &[String]) -> Result<Vec<api::SearchResult>> {
+        let index = self.file_index()?;
+        let locks = self.locks.lock().unwrap();
+        Ok(files
+            .iter()
+            .map(|f| {
+                let mut entry = api::SearchResult {
+                    path: f.clone(),
+                    score: 0,
+                    indices: Vec::new(),
+                    lfs: false,
+                    lockable: false,
+                    lock: locks.iter().find(|l| &l.path == f).cloned(),
+                };
+                index.tag(&mut entry);
+                entry
+            })
+            .collect())
+    }
+}
+
+fn page<T: Clone>(items: &[T], offset: usize, limit: Option<usize>) -> Vec<T> {
+    items
+        .iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .cloned()
+        .collect()
+}
+
+fn pick_repo() -> Option<PathBuf> {
+    let p = match nfd2::open_pick_folder(None).unwrap() {
+        Response::Okay(p) => p,
+        Response::OkayMultiple(p) => p[0].clone(),
+        Response::Cancel => return None,
+    };
+
+    std::fs::read_dir(&p)
+        .ok()?
+        .into_iter()
+        .map(|p| p.unwrap().path())
+        .find(|p| p.file_name().unwrap().eq(".git"))
+        .map(|_| p)
+}
+
+/// Asks where to save an export, `None` if cancelled.
+fn pick_export_path(format: api::ExportFormat) -> Option<PathBuf> {
+    let path = match nfd2::open_save_dialog(Some(format.extension()), None).ok()? {
+        Response::Okay(p) => p,
+        Response::OkayMultiple(p) => p.into_iter().next()?,
+        Response::Cancel => return None,
+    };
+    match path.extension() {
+        Some(_) => Some(path),
+        None => Some(path.with_extension(format.extension())),
+    }
+}
+
+pub(crate) fn check_version(version: u32) -> Result<()> {
+    match (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "protocol version {} is not supported, expected {} to {}",
+            version,
+            MIN_PROTOCOL_VERSION,
+            PROTOCOL_VERSION
+        )),
+    }
+}
+
+/// Runs an enveloped request, the response gets the request's id.
+pub fn dispatch(state: &State, envelope: Envelope<Request>) -> Result<Envelope<api::Response>> {
+    check_version(envelope.version)?;
+    Ok(Envelope {
+        version: envelope.version,
+        id: envelope.id,
+        payload: handle(state, envelope.payload)?,
+    })
+}
+
+/// Runs a single request against the backend state.
+pub fn handle(state: &State, request: Request) -> Result<api::Response> {
+    match request {
+        Request::Hello { version } => {
+            let version = version.min(PROTOCOL_VERSION);
+            check_version(version)?;
+            Ok(api::Response::Hello {
+                version,
+                repo: state.repo.read().unwrap().clone(),
+            })
+        }
+        Request::Echo { message } => {
+            println!("{}", message);
+            Ok(api::Response::Echo { message })
+        }
+        Request::PickRepo => match pick_repo() {
+            None => Ok(api::Response::PickRepo {
+                path: String::new(),
+            }),
+            Some(p) => {
+                state.open_repo(&p)?;
+                let repo = state.repo.read().unwrap().clone();
+                Ok(api::Response::PickRepo { path: repo })
+            }
+        },
+        Request::GetLockedFiles {
+            offset,
+            limit,
+            cached,
+        } => {
+            println!("getting locked files");
+            if !cached {
+                state.refresh_locks()?;
+            }
+            let locks = state.locks.lock().unwrap();
+            Ok(api::Response::GetLockedFiles {
+                locked_files: page(&locks, offset, limit),
+                total: locks.len(),
+                offline: state.offline.lock().unwrap().clone(),
+            })
+        }
+        Request::GetFilteredFiles {
+            filter,
+            offset,
+            limit,
+            all_files,
+        } => {
+            println!("getting filter files");
+
+            let query = Query::parse(&filter)?;
+            let own_locks = match query.needs_own_locks() {
+                true => Some(state.own_locks()?),
+                false => None,
+            };
+            let modified = match query.needs_status() {
+                true => Some(git::get_modified_files(&*state.runner, &state.repo_path())?),
+                false => None,
+            };
+            let index = state.file_index()?;
+            let mut results = match all_files {
+                true => search::rank(&state.matcher.lock().unwrap(), &index.tracked, &query.text),
+                false => search::rank(
+                    &state.matcher.lock().unwrap(),
+                    &state.lfs_files.lock().unwrap(),
+                    &query.text,
+                ),
+            };
+            let locks = state.locks.lock().unwrap();
+            let context = Context {
+                locks: locks
+                    .iter()
+                    .map(|l| (l.path.as_str(), l))
+                    .collect::<HashMap<_, _>>(),
+                own_locks: own_locks.as_ref(),
+                modified: modified.as_ref(),
+                now: chrono::Utc::now(),
+            };
+            results.retain(|r| query.matches(&r.path, &context));
+            let total = results.len();
+            let mut filtered_files = page(&results, offset, limit);
+            for r in filtered_files.iter_mut() {
+                index.tag(r);
+                r.lock = context.locks.get(r.path.as_str()).map(|&l| l.clone());
+            }
+            Ok(api::Response::GetFilteredFiles {
+                filtered_files,
+                total,
+            })
+        }
+        Request::LockFile { path } => {
+            println!("received lock request");
+            let operation = api::PendingOperation {
+                path: path.clone(),
+                lock: true,
+                id: None,
+                time: chrono::Utc::now().to_rfc3339(),
+            };
+            let locked = state.or_pending(operation.clone(), || state.lock_file(&path))?;
+            let lock_entry = match locked {
+                Some(lock_entry) => lock_entry,
+                None => return Ok(api::Response::Pending { operation }),
+            };
+            if let Some(own_locks) = state.own_locks.lock().unwrap().as_mut() {
+                own_locks.insert(lock_entry.id.clone());
+            }
+            {
+                let mut locks = state.locks.lock().unwrap();
+                let at = locks.partition_point(|l| l.path < lock_entry.path);
+                locks.insert(at, lock_entry.clone());
+            }
+            state.events.emit(api::Event::LockAdded {
+                lock: lock_entry.clone(),
+            });
+            state.touch(&path);
+            Ok(api::Response::LockFile { lock_entry })
+        }
+        Request::UnlockFile { id } => {
+            println!("received unlock request");
+            let path = state
+                .locks
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|l| l.id == id.to_string())
+                .map(|l| l.path.clone());
+            match path {
+                Some(path) => {
+                    let operation = api::PendingOperation {
+                        path,
+                        lock: false,
+                        id: Some(id.to_string()),
+                        time: chrono::Utc::now().to_rfc3339(),
+                    };
+                    let unlocked = state.or_pending(operation.clone(), || state.unlock_file(id))?;
+                    if unlocked.is_none() {
+                        return Ok(api::Response::Pending { operation });
+                    }
+                }
+                // not listed, so it can't be kept for later either
+                None => state.unlock_file(id)?,
+            }
+            if let Some(own_locks) = state.own_locks.lock().unwrap().as_mut() {
+                own_locks.remove(&id.to_string());
+            }
+            let lock = {
+                let mut locks = state.locks.lock().unwrap();
+                let at = locks.iter().position(|l| l.id == id.to_string());
+                at.map(|at| locks.remove(at))
+            };
+            if let Some(lock) = lock {
+                state.touch(&lock.path);
+                state.events.emit(api::Event::LockRemoved { lock });
+            }
+            Ok(api::Response::UnlockFile { id })
+        }
+        Request::GetTree { dir } => {
+            let nodes = tree::children(
+                &state.lfs_files.lock().unwrap(),
+                &state.locks.lock().unwrap(),
+                &dir,
+            );
+            Ok(api::Response::GetTree { dir, nodes })
+        }
+        Request::LockFolder { dir } => {
+            println!("locking folder {}", dir);
+            state.refresh_locks()?;
+            let locked: HashSet<String> = state
+                .locks
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|l| l.path.clone())
+                .collect();
+            let files: Vec<String> = tree::files_in(&state.lfs_files.lock().unwrap(), &dir)
+                .filter(|f| !locked.contains(*f))
+                .cloned()
+                .collect();
+            let mut result = api::BatchResult::default();
+            let job = format!("Lock {}", dir);
+            let total = files.len();
+            for file in files {
+                match state.lock_file(&file) {
+                    Ok(_) => result.done.push(file),
+                    Err(e) => result.failed.push(api::BatchFailure {
+                        path: file,
+                        error: e.to_string(),
+                    }),
+                }
+                state.progress(&job, &result, total);
+            }
+            state.refresh_locks()?;
+            Ok(api::Response::LockFolder { result })
+        }
+        Request::UnlockFolder { dir } => {
+            println!("unlocking folder {}", dir);
+            state.refresh_locks()?;
+            let own_locks = state.own_locks()?;
+            let locks: Vec<api::LockEntry> = state
+                .locks
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|l| own_locks.contains(&l.id) && tree::contains(&dir, &l.path))
+                .cloned()
+                .collect();
+            let result = state.unlock_all(&format!("Unlock {}", dir), locks);
+            state.refresh_locks()?;
+            Ok(api::Response::UnlockFolder { result })
+        }
+        Request::PreviewUnlockMine => {
+            state.refresh_locks()?;
+            let own_locks = state.own_locks()?;
+            let modified = git::get_modified_files(&*state.runner, &state.repo_path())?;
+            let locks = state
+                .locks
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|l| own_locks.contains(&l.id))
+                .map(|l| api::UnlockPreview {
+                    lock: l.clone(),
+                    modified: modified.contains(&l.path),
+                })
+                .collect();
+            Ok(api::Response::PreviewUnlockMine { locks })
+        }
+        Request::UnlockMine { ids } => {
+            println!("unlocking {} own locks", ids.len());
+            let own_locks = state.own_locks()?;
+            let mut result = api::BatchResult::default();
+            let mut locks = Vec::new();
+            for id in ids {
+                let id = id.to_string();
+                let lock = state
+                    .locks
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|l| l.id == id)
+                    .cloned();
+                match lock {
+                    Some(l) if own_locks.contains(&id) => locks.push(l),
+                    Some(l) => result.failed.push(api::BatchFailure {
+                        path: l.path,
+                        error: format!("lock {} is held by {}", id, l.owner.name),
+                    }),
+                    // no path to show, the id stands in for it
+                    None => result.failed.push(api::BatchFailure {
+                        path: id,
+                        error: "lock not found".to_string(),
+                    }),
+                }
+            }
+            let unlocked = state.unlock_all("Unlock all mine", locks);
+            result.done = unlocked.done;
+            result.failed.extend(unlocked.failed);
+            state.refresh_locks()?;
+            Ok(api::Response::UnlockMine { result })
+        }
+        Request::GetQuickAccess => {
+            let repo = state.repo.read().unwrap().clone();
+            let files = state.quick_access.lock().unwrap().files(&repo);
+            Ok(api::Response::GetQuickAccess {
+                pinned: state.quick_access_entries(&files.pinned)?,
+                recent: state.quick_access_entries(&files.recent)?,
+                watched: state.quick_access_entries(&files.watched)?,
+                queued: state.quick_access_entries(&files.queued)?,
+                pending: state.offline_queue.lock().unwrap().pending(&repo),
+            })
+        }
+        Request::PinFile { path, pinned } => {
+            let repo = state.repo.read().unwrap().clone();
+            state
+                .quick_access
+                .lock()
+                .unwrap()
+                .pin(&repo, &path, pinned)?;
+            Ok(api::Response::PinFile { path, pinned })
+        }
+        Request::WatchFile { path, watched } => {
+            let repo = state.repo.read().unwrap().clone();
+            state
+                .quick_access
+                .lock()
+                .unwrap()
+                .watch(&repo, &path, watched)?;
+            Ok(api::Response::WatchFile { path, watched })
+        }
+        Request::QueueLock { path, queued } => {
+            println!("queueing lock for {}: {}", path, queued);
+            let repo = state.repo.read().unwrap().clone();
+            state
+                .quick_access
+                .lock()
+                .unwrap()
+                .queue(&repo, &path, queued)?;
+            if queued {
+                // takes the lock right away if it is already free
+                state.refresh_locks()?;
+            }
+            Ok(api::Response::QueueLock { path, queued })
+        }
+        Request::CheckPushedLocks => {
+            let (pending, released) = state.release_pushed(state.release_mode)?;
+            Ok(api::Response::CheckPushedLocks { pending, released })
+        }
+        Request::GetInbox => Ok(api::Response::GetInbox {
+            events: state.inbox.lock().unwrap().clone(),
+        }),
+        Request::ClearInbox => {
+            state.inbox.lock().unwrap().clear();
+            Ok(api::Response::ClearInbox)
+        }
+        Request::GetKeyBindings => {
+            let bindings = api::Command::ALL
+                .iter()
+                .map(|c| {
+                    let keys = match state.keys.get(c) {
+                        Some(keys) => keys.clone(),
+                        None => c.default_keys().to_string(),
+                    };
+                    (*c, keys)
+                })
+                .collect();
+            Ok(api::Response::GetKeyBindings { bindings })
+        }
+        Request::GetLocale => Ok(api::Response::GetLocale {
+            locale: state.locale.clone(),
+        }),
+        Request::ExportLocks {
+            filter,
+            format,
+            path,
+        } => {
+            let path = match path.map(PathBuf::from).or_else(|| pick_export_path(format)) {
+                Some(path) => path,
+                None => {
+                    return Ok(api::Response::ExportLocks {
+                        path: None,
+                        count: 0,
+                        contents: None,
+                    })
+                }
+            };
+            let (contents, count) = state.export_locks(&filter, format)?;
+            std::fs::write(&path, contents)
+                .map_err(|e| anyhow!("failed to write {:?}, {}", path, e))?;
+            Ok(api::Response::ExportLocks {
+                path: Some(path.to_string_lossy().to_string()),
+                count,
+                contents: None,
+            })
+        }
+        Request::GetLockStats { stale_days } => Ok(api::Response::GetLockStats {
+            stats: state.lock_stats(stale_days.unwrap_or(state.stale_days))?,
+        }),
+        Request::AnswerPrompt { id, answer } => {
+            let askpass = state
+                .askpass
+                .as_ref()
+                .ok_or(anyhow!("no prompts are relayed"))?;
+            askpass.answer(id, answer)?;
+            Ok(api::Response::AnswerPrompt { id })
+        }
+        Request::CancelPending { path } => {
+            let repo = state.repo.read().unwrap().clone();
+            state.offline_queue.lock().unwrap().cancel(&repo, &path)?;
+            Ok(api::Response::CancelPending { path })
+        }
+    }
+}
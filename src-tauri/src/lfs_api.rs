@@ -0,0 +1,481 @@
+//! Direct calls to the lfs locking api, for servers where `git lfs` would sit waiting
+//! for a password nobody can type. Credentials come from git's credential helpers,
+//! or from `git-lfs-authenticate` for ssh remotes.
+
+use crate::credential::{self, Credential, MemoryStore, TokenStore};
+use crate::network::{self, NetworkConfig, NetworkSettings, Unreachable};
+use crate::runner::{CommandRunner, GitCommand};
+use anyhow::{anyhow, Result};
+use backend_api as api;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const MEDIA_TYPE: &str = "application/vnd.git-lfs+json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LfsSettings {
+    /// Calls the locking api directly instead of running `git lfs`.
+    pub direct_api: bool,
+    /// Keeps `git-lfs-authenticate` tokens in the secret store of the OS instead
+    /// of memory, needs a build with the `secret-store` feature.
+    pub secret_store: bool,
+}
+
+impl LfsSettings {
+    pub fn token_store(&self) -> Arc<dyn TokenStore> {
+        #[cfg(feature = "secret-store")]
+        {
+            if self.secret_store {
+                return Arc::new(credential::SecretStore);
+            }
+        }
+        if self.secret_store {
+            println!("built without the secret-store feature, keeping tokens in memory");
+        }
+        Arc::new(MemoryStore::default())
+    }
+}
+
+/// An ssh remote, whose api access comes from `git-lfs-authenticate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshRemote {
+    /// `user@host` or `host`.
+    pub user_host: String,
+    pub port: Option<String>,
+    /// Repo path on the host, like `team/game.git`.
+    pub path: String,
+}
+
+impl SshRemote {
+    fn token_key(&self, operation: &str) -> String {
+        format!("{}:{}/{}", self.user_host, self.path, operation)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    /// Base url of the api, `git-lfs-authenticate` may answer with another.
+    pub url: String,
+    pub ssh: Option<SshRemote>,
+}
+
+impl Endpoint {
+    /// The endpoint git lfs derives from a remote url.
+    pub fn from_remote(remote: &str) -> Result<Self> {
+        let lfs_url = |host: &str, path: &str| {
+            let path = path.trim_matches('/');
+            match path.ends_with(".git") {
+                true => format!("https://{}/{}/info/lfs", host, path),
+                false => format!("https://{}/{}.git/info/lfs", host, path),
+            }
+        };
+        if remote.starts_with("https://") || remote.starts_with("http://") {
+            let url = match remote.trim_end_matches('/').ends_with(".git") {
+                true => format!("{}/info/lfs", remote.trim_end_matches('/')),
+                false => format!("{}.git/info/lfs", remote.trim_end_matches('/')),
+            };
+            return Ok(Self { url, ssh: None });
+        }
+        let (user_host, port, path) = match remote.strip_prefix("ssh://") {
+            Some(rest) => {
+                let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+                match authority.rsplit_once(':') {
+                    Some((user_host, port)) => (user_host, Some(port.to_string()), path),
+                    None => (authority, None, path),
+                }
+            }
+            // scp like `git@host:team/game.git`
+            None => match remote.split_once(':') {
+                Some((user_host, path)) if !user_host.contains('/') => (user_host, None, path),
+                _ => return Err(anyhow!("no lfs api for remote {}", remote)),
+            },
+        };
+        let host = user_host.rsplit('@').next().unwrap();
+        Ok(Self {
+            url: lfs_url(host, path),
+            ssh: Some(SshRemote {
+                user_host: user_host.to_string(),
+                port,
+                path: path.to_string(),
+            }),
+        })
+    }
+}
+
+/// What `git-lfs-authenticate` answers.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SshAuth {
+    pub href: String,
+    #[serde(default)]
+    pub header: HashMap<String, String>,
+    /// RFC 3339, a token without one is used until the server refuses it.
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    #[serde(default)]
+    pub expires_in: Option<i64>,
+}
+
+fn config(runner: &dyn CommandRunner, repo: &Path, key: &str) -> Result<Option<String>> {
+    let command = GitCommand::new(repo).args(["config", "--get", key]);
+    let output = runner
+        .run(&command)
+        .map_err(|e| anyhow!("failed to run git config, {}", e))?;
+    // exits with 1 for unset keys
+    Ok(Some(output.stdout.trim().to_string()).filter(|v| output.success && !v.is_empty()))
+}
+
+/// The api endpoint of `repo`, from `lfs.url` or the `origin` remote.
+pub fn endpoint(runner: &dyn CommandRunner, repo: &Path) -> Result<Endpoint> {
+    if let Some(url) = config(runner, repo, "lfs.url")? {
+        return match url.starts_with("https://") || url.starts_with("http://") {
+            true => Ok(Endpoint { url, ssh: None }),
+            false => Endpoint::from_remote(&url),
+        };
+    }
+    let remote = match config(runner, repo, "remote.origin.lfsurl")? {
+        Some(url) => return Ok(Endpoint { url, ssh: None }),
+        None => config(runner, repo, "remote.origin.url")?,
+    };
+    Endpoint::from_remote(&remote.ok_or(anyhow!("{:?} has no origin remote", repo))?)
+}
+
+/// The shell git runs ssh commands with, the one git for windows bundles or else `sh`
+/// on `PATH`.
+fn shell(runner: &dyn CommandRunner, repo: &Path) -> Result<Option<PathBuf>> {
+    let command = GitCommand::new(repo).args(["--exec-path"]);
+    let output = runner
+        .run(&command)
+        .map_err(|e| anyhow!("failed to run git --exec-path, {}", e))?;
+    // like `C:/Program Files/Git/mingw64/libexec/git-core`
+    let exec_path = PathBuf::from(output.stdout.trim());
+    let bundled = exec_path.ancestors().nth(3).and_then(|root| {
+        ["usr/bin/sh.exe", "bin/sh.exe"]
+            .iter()
+            .map(|sh| root.join(sh))
+            .find(|sh| sh.is_file())
+    });
+    if bundled.is_some() {
+        return Ok(bundled);
+    }
+    let on_path = std::env::var_os("PATH").map_or(false, |paths| {
+        std::env::split_paths(&paths)
+            .any(|dir| dir.join("sh").is_file() || dir.join("sh.exe").is_file())
+    });
+    Ok(Some(PathBuf::from("sh")).filter(|_| on_path))
+}
+
+/// The ssh program with its arguments, from `GIT_SSH_COMMAND`, `core.sshCommand` or
+/// `GIT_SSH` like git picks it.
+fn ssh_command(runner: &dyn CommandRunner, repo: &Path) -> Result<GitCommand> {
+    let ssh = match std::env::var("GIT_SSH_COMMAND") {
+        Ok(ssh) => Some(ssh),
+        Err(_) => config(runner, repo, "core.sshCommand")?,
+    };
+    let ssh = match ssh {
+        Some(ssh) => ssh,
+        None => {
+            let program = std::env::var("GIT_SSH").unwrap_or_else(|_| "ssh".to_string());
+            return Ok(GitCommand::new(repo).program(program));
+        }
+    };
+    Ok(match shell(runner, repo)? {
+        // through the shell like git does, so quoted arguments survive
+        Some(sh) => GitCommand::new(repo).program(sh.to_string_lossy()).args([
+            "-c",
+            &format!("{} \"$@\"", ssh),
+            &ssh,
+        ]),
+        None => {
+            let mut words = ssh.split_whitespace();
+            GitCommand::new(repo)
+                .program(words.next().unwrap_or("ssh"))
+                .args(words)
+        }
+    })
+}
+
+fn ssh_auth(
+    runner: &dyn CommandRunner,
+    repo: &Path,
+    remote: &SshRemote,
+    operation: &str,
+) -> Result<SshAuth> {
+    let mut command = ssh_command(runner, repo)?;
+    if let Some(port) = &remote.port {
+        command = command.args(["-p", port]);
+    }
+    let command = command.args([
+        remote.user_host.as_str(),
+        "git-lfs-authenticate",
+        remote.path.as_str(),
+        operation,
+    ]);
+    let output = runner
+        .run(&command)
+        .map_err(|e| anyhow!("failed to run {}, {}", command.args.join(" "), e))?;
+    if !output.success {
+        return Err(anyhow!(
+            "git-lfs-authenticate on {} failed, {}",
+            remote.user_host,
+            output.stderr.trim()
+        ));
+    }
+    let mut auth: SshAuth = serde_json::from_str(&output.stdout)
+        .map_err(|e| anyhow!("failed to parse git-lfs-authenticate answer, {}", e))?;
+    if let Some(seconds) = auth.expires_in.take() {
+        auth.expires_at = Some((Utc::now() + Duration::seconds(seconds)).to_rfc3339());
+    }
+    Ok(auth)
+}
+
+fn request(
+    network: &NetworkConfig,
+    method: &str,
+    url: &str,
+    headers: &HashMap<String, String>,
+    credential: Option<&Credential>,
+    body: Option<&serde_json::Value>,
+) -> Result<ureq::Response> {
+    let mut request = ureq::request(method, url);
+    request.set("Accept", MEDIA_TYPE).timeout_connect(10_000);
+    if let Some(proxy) = network.proxy()? {
+        request.set_proxy(proxy);
+    }
+    if let Some(tls) = network.tls_config()? {
+        request.set_tls_config(tls);
+    }
+    for (name, value) in headers {
+        request.set(name, value);
+    }
+    if let Some(c) = credential {
+        request.auth(
+            c.username.as_deref().unwrap_or_default(),
+            c.password.as_deref().unwrap_or_default(),
+        );
+    }
+    let response = match body {
+        Some(body) => request
+            .set("Content-Type", MEDIA_TYPE)
+            .send_string(&body.to_string()),
+        None => request.call(),
+    };
+    if !response.synthetic() {
+        return Ok(response);
+    }
+    let error = response.into_synthetic_error().unwrap();
+    let message = format!("failed to reach {}, {}", url, error);
+    match network::is_connection_error(&error) {
+        true => Err(Unreachable(message).into()),
+        false => Err(anyhow!(message)),
+    }
+}
+
+/// Percent-encodes `value` for a query string, cursors are opaque to clients.
+fn query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct Locks {
+    locks: Vec<api::LockEntry>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LockReply {
+    lock: api::LockEntry,
+}
+
+#[derive(Deserialize)]
+struct VerifiedLocks {
+    ours: Vec<api::LockEntry>,
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+/// Talks to the locking api of whatever repo it is given, with tokens from `store`.
+pub struct LfsClient {
+    store: Arc<dyn TokenStore>,
+    network: NetworkSettings,
+}
+
+impl LfsClient {
+    pub fn new(store: Arc<dyn TokenStore>, network: NetworkSettings) -> Self {
+        Self { store, network }
+    }
+
+    /// A cached token for `remote` while it is valid, else a new one.
+    fn token(
+        &self,
+        runner: &dyn CommandRunner,
+        repo: &Path,
+        remote: &SshRemote,
+        operation: &str,
+    ) -> Result<SshAuth> {
+        let key = remote.token_key(operation);
+        let cached = self
+            .store
+            .get(&key)
+            .and_then(|t| serde_json::from_str::<SshAuth>(&t).ok())
+            .filter(|a| match &a.expires_at {
+                Some(at) => DateTime::parse_from_rfc3339(at).map_or(false, |at| at > Utc::now()),
+                None => true,
+            });
+        if let Some(auth) = cached {
+            return Ok(auth);
+        }
+        let auth = ssh_auth(runner, repo, remote, operation)?;
+        self.store.set(&key, &serde_json::to_string(&auth)?);
+        Ok(auth)
+    }
+
+    /// Sends a request to `path` below the api url, `operation` is what it is
+    /// authenticated for over ssh, `download` or `upload`.
+    fn send(
+        &self,
+        runner: &dyn CommandRunner,
+        repo: &Path,
+        operation: &str,
+        method: &str,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let endpoint = endpoint(runner, repo)?;
+        let network = self.network.for_repo(repo.to_str().unwrap_or_default());
+        let (base, headers) = match &endpoint.ssh {
+            Some(remote) => {
+                let auth = self.token(runner, repo, remote, operation)?;
+                (auth.href, auth.header)
+            }
+            None => (endpoint.url, HashMap::new()),
+        };
+        let url = format!("{}/{}", base.trim_end_matches('/'), path);
+        let mut response = request(&network, method, &url, &headers, None, body.as_ref())?;
+        if response.status() == 401 {
+            match &endpoint.ssh {
+                // revoked before it expired, ask for another once
+                Some(remote) => {
+                    self.store.remove(&remote.token_key(operation));
+                    let auth = self.token(runner, repo, remote, operation)?;
+                    let url = format!("{}/{}", auth.href.trim_end_matches('/'), path);
+                    response = request(&network, method, &url, &auth.header, None, body.as_ref())?;
+                }
+                None => {
+                    let filled = credential::fill(runner, repo, &Credential::for_url(&url)?)?;
+                    response = request(
+                        &network,
+                        method,
+                        &url,
+                        &headers,
+                        Some(&filled),
+                        body.as_ref(),
+                    )?;
+                    match response.status() {
+                        401 => credential::reject(runner, repo, &filled)?,
+                        s if s < 400 => credential::approve(runner, repo, &filled)?,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let status = response.status();
+        let text = response
+            .into_string()
+            .map_err(|e| anyhow!("failed to read answer of {}, {}", url, e))?;
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+        match status {
+            s if s < 400 => Ok(json),
+            s => Err(anyhow!(
+                "{} {} failed with {}, {}",
+                method,
+                url,
+                s,
+                json["message"].as_str().unwrap_or(text.trim())
+            )),
+        }
+    }
+
+    pub fn get_locked_files(
+        &self,
+        runner: &dyn CommandRunner,
+        repo: &Path,
+    ) -> Result<Vec<api::LockEntry>> {
+        let mut locks = Vec::new();
+        let mut path = "locks".to_string();
+        loop {
+            let json = self.send(runner, repo, "download", "GET", &path, None)?;
+            let page: Locks = serde_json::from_value(json)
+                .map_err(|e| anyhow!("failed to parse locks, {}", e))?;
+            locks.extend(page.locks);
+            match page.next_cursor.filter(|c| !c.is_empty()) {
+                Some(cursor) => path = format!("locks?cursor={}", query_value(&cursor)),
+                None => return Ok(locks),
+            }
+        }
+    }
+
+    /// Ids of the locks owned by the user the server authenticates us as.
+    pub fn get_own_lock_ids(
+        &self,
+        runner: &dyn CommandRunner,
+        repo: &Path,
+    ) -> Result<HashSet<String>> {
+        let mut ids = HashSet::new();
+        let mut body = serde_json::json!({});
+        loop {
+            let json = self.send(runner, repo, "upload", "POST", "locks/verify", Some(body))?;
+            let page: VerifiedLocks = serde_json::from_value(json)
+                .map_err(|e| anyhow!("failed to parse verified locks, {}", e))?;
+            ids.extend(page.ours.into_iter().map(|l| l.id));
+            match page.next_cursor.filter(|c| !c.is_empty()) {
+                Some(cursor) => body = serde_json::json!({ "cursor": cursor }),
+                None => return Ok(ids),
+            }
+        }
+    }
+
+    pub fn lock_file(
+        &self,
+        runner: &dyn CommandRunner,
+        repo: &Path,
+        file: &str,
+    ) -> Result<api::LockEntry> {
+        let json = self.send(
+            runner,
+            repo,
+            "upload",
+            "POST",
+            "locks",
+            Some(serde_json::json!({ "path": file })),
+        )?;
+        let reply: LockReply = serde_json::from_value(json)
+            .map_err(|e| anyhow!("failed to parse lock of {}, {}", file, e))?;
+        Ok(reply.lock)
+    }
+
+    pub fn unlock_file(&self, runner: &dyn CommandRunner, repo: &Path, id: u32) -> Result<()> {
+        self.send(
+            runner,
+            repo,
+            "upload",
+            "POST",
+            &format!("locks/{}/unlock", id),
+            Some(serde_json::json!({ "force": false })),
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+//! Backend of the lock manager, shared by the gui binary and the integration tests.
+
+pub mod api_server;
+pub mod askpass;
+pub mod credential;
+pub mod events;
+pub mod export;
+pub mod git;
+pub mod handler;
+pub mod lfs_api;
+pub mod network;
+pub mod notification;
+pub mod offline_queue;
+pub mod query;
+pub mod quick_access;
+pub mod release;
+pub mod runner;
+pub mod search;
+pub mod settings;
+pub mod snapshot;
+pub mod stats;
+pub mod tree;
+pub mod watch;
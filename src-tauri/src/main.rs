@@ -3,252 +3,265 @@
     windows_subsystem = "windows"
 )]
 
-use anyhow::{anyhow, Result};
 use backend_api as api;
-use backend_api::{LockEntry, Request};
-use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
-use nfd2::Response;
-use serde_json::Error;
-use std::os::windows::process::CommandExt;
-use std::path::{Path, PathBuf};
-use std::process::ExitStatus;
-use std::sync::{Arc, Mutex, RwLock};
+use git_lock_manager::askpass::{self, Askpass};
+use git_lock_manager::events::{EventBus, EventSink};
+use git_lock_manager::lfs_api::LfsClient;
+use git_lock_manager::notification::DesktopNotifier;
+use git_lock_manager::offline_queue::OfflineQueue;
+use git_lock_manager::quick_access::QuickAccess;
+use git_lock_manager::release::{self, ReleaseMode};
+use git_lock_manager::runner::ProcessRunner;
+use git_lock_manager::settings::Settings;
+use git_lock_manager::snapshot::Snapshots;
+use git_lock_manager::{api_server, handler, stats, watch};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-fn pick_repo() -> Option<std::path::PathBuf> {
-    let p = match nfd2::open_pick_folder(None).unwrap() {
-        Response::Okay(p) => p,
-        Response::OkayMultiple(p) => p[0].clone(),
-        Response::Cancel => return None,
-    };
+/// What tauri's `promisified` sends, its promise callbacks next to the envelope.
+#[derive(Deserialize)]
+struct TauriCall {
+    callback: String,
+    error: String,
+    #[serde(flatten)]
+    envelope: api::Envelope<api::Request>,
+}
+
+/// Forwards backend events to the webview as `api::EVENT_NAME`.
+struct WebviewSink(Mutex<tauri::WebviewMut>);
 
-    std::fs::read_dir(&p)
-        .ok()?
-        .into_iter()
-        .map(|p| p.unwrap().path())
-        .find(|p| p.file_name().unwrap().eq(".git"))
-        .map(|_| p)
+impl EventSink for WebviewSink {
+    fn emit(&self, event: &api::Event) {
+        let mut webview = self.0.lock().unwrap();
+        if let Err(e) = tauri::event::emit(&mut webview, api::EVENT_NAME, Some(event.clone())) {
+            println!("failed to emit {:?}, {}", event, e);
+        }
+    }
 }
 
-fn get_lfs_files(path: &Path) -> Vec<String> {
-    let output = std::process::Command::new("git")
-        .arg("lfs")
-        .arg("ls-files")
-        .arg("-n")
-        .creation_flags(winapi::um::winbase::CREATE_NO_WINDOW)
-        .current_dir(&path)
-        .output()
-        .expect("failed to run git lfs ls-files");
-    String::from_utf8(output.stdout)
-        .unwrap()
-        .lines()
-        .map(|s| String::from(s))
-        .collect()
+struct Options {
+    api_server: bool,
+    api_port: u16,
+    headless: bool,
+    release_pushed: bool,
+    install_hook: bool,
+    stats: bool,
 }
 
-fn get_locked_files(path: &str) -> Vec<String> {
-    println!("getting files");
-    let output = std::process::Command::new("git")
-        .arg("lfs")
-        .arg("locks")
-        .current_dir(&path)
-        .creation_flags(winapi::um::winbase::CREATE_NO_WINDOW)
-        .output()
-        .expect("failed to run git lfs locks");
-    String::from_utf8(output.stdout)
-        .unwrap()
-        .lines()
-        .map(|s| String::from(s))
-        .collect()
+fn parse_options() -> Options {
+    let mut options = Options {
+        api_server: false,
+        api_port: api_server::DEFAULT_PORT,
+        headless: false,
+        release_pushed: false,
+        install_hook: false,
+        stats: false,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--api-server" => options.api_server = true,
+            "--api-port" => {
+                options.api_port = args
+                    .next()
+                    .and_then(|p| p.parse().ok())
+                    .expect("--api-port expects a port number")
+            }
+            "--headless" => {
+                options.api_server = true;
+                options.headless = true;
+            }
+            "--release-pushed" => options.release_pushed = true,
+            "--install-hook" => options.install_hook = true,
+            "--stats" => options.stats = true,
+            _ => println!("ignoring unknown argument {}", arg),
+        }
+    }
+    options
 }
 
-fn lock_file(repo: &str, file: &str) -> Option<api::LockEntry> {
-    let repo = repo.to_string();
-    let file = file.to_string();
-    println!("locking {}", file);
-    let output = std::process::Command::new("git")
-        .arg("lfs")
-        .arg("lock")
-        .arg(&file)
-        .arg("--json")
-        .current_dir(&repo)
-        .creation_flags(winapi::um::winbase::CREATE_NO_WINDOW)
-        .output()
-        .expect(format!("failed to lock {}", file).as_str());
-    match serde_json::from_str::<Vec<api::LockEntry>>(
-        String::from_utf8(output.stdout).unwrap().as_str(),
-    ) {
-        Ok(e) => Some(e[0].clone()),
-        Err(e) => {
-            println!("failed, {}", e);
-            None
+/// `--install-hook`, `--release-pushed` and `--stats`, run in the repo's directory
+/// without a window.
+fn run_command(state: &handler::State, options: &Options) {
+    let repo = state.repo.read().unwrap().clone();
+    if repo.is_empty() {
+        println!("not in a git repository");
+        std::process::exit(1);
+    }
+    if options.stats {
+        match state.lock_stats(state.stale_days) {
+            Ok(stats) => print!("{}", stats::summary(&stats, chrono::Utc::now())),
+            Err(e) => {
+                println!("failed to get locks, {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if options.install_hook {
+        let exe = std::env::current_exe().unwrap();
+        match release::install_hook(&*state.runner, repo.as_ref(), &exe) {
+            Ok(path) => println!("installed {:?}", path),
+            Err(e) => {
+                println!("failed to install hook, {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if options.release_pushed {
+        // running it or installing the hook is the opt in, so no prompt here
+        match state.release_pushed(ReleaseMode::Auto) {
+            Ok((_, result)) => {
+                result.done.iter().for_each(|p| println!("unlocked {}", p));
+                for f in &result.failed {
+                    println!("failed to unlock {}, {}", f.path, f.error);
+                }
+            }
+            Err(e) => {
+                println!("failed to release pushed locks, {}", e);
+                std::process::exit(1);
+            }
         }
     }
 }
 
-fn unlock_file(repo: &str, id: u32) {
-    let repo = repo.to_string();
-    println!("unlocking {}", id);
-    let output = std::process::Command::new("git")
-        .arg("lfs")
-        .arg("unlock")
-        .arg("-i")
-        .arg(id.to_string())
-        .current_dir(&repo)
-        .creation_flags(winapi::um::winbase::CREATE_NO_WINDOW)
-        .output()
-        .expect(format!("failed to unlock {}", id).as_str());
+/// Answers the prompt git or ssh started us with, in the app that set [`askpass::ENV`].
+fn run_askpass_helper(connect: &str, prompt: &str) -> ! {
+    match askpass::ask_app(connect, prompt) {
+        Ok(Some(answer)) => {
+            println!("{}", answer);
+            std::process::exit(0);
+        }
+        Ok(None) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("failed to ask git-lock-manager, {}", e);
+            std::process::exit(1);
+        }
+    }
 }
 
 fn main() {
+    if let Ok(connect) = std::env::var(askpass::ENV) {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        // hooks run by git inherit the variable, they come with options
+        if let [prompt] = args.as_slice() {
+            if !prompt.starts_with("--") {
+                run_askpass_helper(&connect, prompt);
+            }
+        }
+    }
+    let options = parse_options();
     let current_dir = std::env::current_dir().unwrap_or_default();
     // let current_dir = current_dir.parent().unwrap();
     println!("current_dir {}", current_dir.to_str().unwrap());
-    let lfs_files: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
     let mut default_path = String::new();
     if let Ok(dir) = std::fs::read_dir(&current_dir) {
         for entry in dir.flatten() {
             if let Some(file_name) = entry.path().file_name() {
                 if file_name.eq(".git") {
                     default_path = current_dir.to_str().unwrap().to_owned();
-                    *lfs_files.lock().unwrap() = get_lfs_files(&current_dir);
                 }
             }
         }
     }
-    let repo = Arc::new(RwLock::new(default_path));
-    dbg!(&repo);
-    let repo_handler = repo.clone();
-    let lfs_files_handler = lfs_files.clone();
-    let matcher = Arc::new(Mutex::new(SkimMatcherV2::default()));
-    let matcher_handler = matcher.clone();
+    let mut settings = Settings::load();
+    let events = Arc::new(EventBus::default());
+    let command = options.install_hook || options.release_pushed || options.stats;
+    // commands run in a terminal that can answer prompts, the gui relays them
+    let askpass = match command || options.headless {
+        true => None,
+        false => Askpass::start(events.clone(), askpass::TIMEOUT)
+            .map_err(|e| println!("failed to start askpass, {}", e))
+            .ok(),
+    };
+    let git_env = &mut settings.git.env;
+    match &askpass {
+        Some(askpass) => {
+            let exe = std::env::current_exe().unwrap();
+            for (key, value) in askpass.env(&exe) {
+                git_env.entry(key).or_insert(value);
+            }
+        }
+        // a prompt nobody can answer would hang the request
+        None if !command => {
+            git_env
+                .entry("GIT_TERMINAL_PROMPT".to_string())
+                .or_insert_with(|| "0".to_string());
+        }
+        None => {}
+    }
+    settings.network.apply(&mut settings.git);
+    let mut state = handler::State::new(default_path, Arc::new(ProcessRunner::new(settings.git)));
+    state.events = events;
+    state.askpass = askpass;
+    if let Ok(path) = QuickAccess::default_path() {
+        state.quick_access = Arc::new(Mutex::new(QuickAccess::load(path)));
+    }
+    if let Ok(path) = Snapshots::default_path() {
+        state.snapshots = Arc::new(Mutex::new(Snapshots::load(path)));
+    }
+    if let Ok(path) = OfflineQueue::default_path() {
+        state.offline_queue = Arc::new(Mutex::new(OfflineQueue::load(path)));
+    }
+    state.notifier = Some(Arc::new(DesktopNotifier));
+    state.release_mode = settings.release.mode;
+    state.keys = settings.keys;
+    state.locale = settings.locale;
+    state.stale_days = settings.stats.stale_days;
+    if settings.lfs.direct_api {
+        state.lfs_api = Some(Arc::new(LfsClient::new(
+            settings.lfs.token_store(),
+            settings.network,
+        )));
+    }
+    if command {
+        run_command(&state, &options);
+        return;
+    }
+    if settings.watch.interval_secs > 0 {
+        watch::spawn_poller(
+            state.clone(),
+            Duration::from_secs(settings.watch.interval_secs),
+        );
+    }
+
+    if options.headless {
+        api_server::serve(state, options.api_port).unwrap();
+        return;
+    }
+    if options.api_server {
+        let state = state.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = api_server::serve(state, options.api_port) {
+                println!("{}", e);
+            }
+        });
+    }
+
+    let events = state.events.clone();
     tauri::AppBuilder::new()
-        .invoke_handler(move |_webview, arg| {
-            let repo_promise = repo_handler.clone();
-            let lfs_files_promise = lfs_files_handler.clone();
-            let matcher_promise = matcher_handler.clone();
-            match serde_json::from_str::<api::Request>(arg) {
+        .setup(move |webview, _source| {
+            events.subscribe(Arc::new(WebviewSink(Mutex::new(webview.as_mut()))));
+        })
+        .invoke_handler(
+            move |_webview, arg| match serde_json::from_str::<TauriCall>(arg) {
                 Err(e) => Err(e.to_string()),
-                Ok(command) => {
-                    match command {
-                        // definitions for your custom commands from Cmd here
-                        api::Request::Echo {
-                            message,
-                            callback,
-                            error,
-                        } => {
-                            //  your command code
-                            println!("{}", message);
-                            tauri::execute_promise(
-                                _webview,
-                                move || {
-                                    Ok(api::Response::PickRepo {
-                                        path: repo_promise.read().unwrap().clone(),
-                                    })
-                                },
-                                callback,
-                                error,
-                            )
-                        }
-                        api::Request::PickRepo { callback, error } => tauri::execute_promise(
-                            _webview,
-                            move || match pick_repo() {
-                                None => Ok(api::Response::PickRepo {
-                                    path: String::new(),
-                                }),
-                                Some(p) => {
-                                    *repo_promise.write().unwrap() =
-                                        String::from(p.to_str().unwrap());
-                                    *lfs_files_promise.lock().unwrap() = get_lfs_files(&p);
-                                    Ok(api::Response::PickRepo {
-                                        path: repo_promise.read().unwrap().clone(),
-                                    })
-                                }
-                            },
-                            callback,
-                            error,
-                        ),
-                        api::Request::GetLockedFiles { callback, error } => tauri::execute_promise(
-                            _webview,
-                            move || {
-                                println!("getting locked files");
-                                Ok(api::Response::GetLockedFiles {
-                                    locked_files: get_locked_files(&*repo_promise.read().unwrap()),
-                                })
-                            },
-                            callback,
-                            error,
-                        ),
-                        Request::GetFilteredFiles {
-                            filter,
-                            callback,
-                            error,
-                        } => tauri::execute_promise(
-                            _webview,
-                            move || {
-                                println!("getting filter files");
-
-                                let filtered_list: Vec<String> = lfs_files_promise
-                                    .lock()
-                                    .unwrap()
-                                    .iter()
-                                    .filter(|f| {
-                                        matcher_promise
-                                            .lock()
-                                            .unwrap()
-                                            .fuzzy_match(
-                                                f.to_lowercase().as_str(),
-                                                &filter.to_lowercase().as_str(),
-                                            )
-                                            .is_some()
-                                    })
-                                    .take(50)
-                                    .cloned()
-                                    .collect();
-                                Ok(api::Response::GetFilteredFiles {
-                                    filtered_files: filtered_list,
-                                })
-                            },
-                            callback,
-                            error,
-                        ),
-                        Request::LockFile {
-                            path,
-                            callback,
-                            error,
-                        } => {
-                            println!("received lock request");
-                            tauri::execute_promise(
-                                _webview,
-                                move || match lock_file(&*repo_promise.read().unwrap(), &path) {
-                                    None => Err(anyhow!("failed to lock file")),
-                                    Some(lock_entry) => Ok(api::Response::LockFile { lock_entry }),
-                                },
-                                callback,
-                                error,
-                            )
-                        }
-                        Request::UnlockFile {
-                            id,
-                            callback,
-                            error,
-                        } => {
-                            println!("received unlock request");
-                            tauri::execute_promise(
-                                _webview,
-                                move || {
-                                    unlock_file(&*repo_promise.read().unwrap(), id);
-                                    Ok(api::Response::UnlockFile { id })
-                                },
-                                callback,
-                                error,
-                            )
-                        }
-                    }
+                Ok(TauriCall {
+                    callback,
+                    error,
+                    envelope,
+                }) => {
+                    let state = state.clone();
+                    tauri::execute_promise(
+                        _webview,
+                        move || handler::dispatch(&state, envelope),
+                        callback,
+                        error,
+                    );
                     Ok(())
                 }
-            }
-        })
+            },
+        )
         .build()
         .run();
 }
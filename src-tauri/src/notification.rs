@@ -0,0 +1,19 @@
+//! Desktop notifications for watch events.
+
+use crate::watch::Notifier;
+use backend_api::WatchEvent;
+
+pub struct DesktopNotifier;
+
+impl Notifier for DesktopNotifier {
+    fn notify(&self, event: &WatchEvent) {
+        let shown = notify_rust::Notification::new()
+            .appname("Git Lock Manager")
+            .summary("Git Lock Manager")
+            .body(&event.describe())
+            .show();
+        if let Err(e) = shown {
+            println!("failed to show notification, {}", e);
+        }
+    }
+}
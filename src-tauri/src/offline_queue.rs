@@ -0,0 +1,77 @@
+//! Locks and unlocks asked for while the lfs server can't be reached, remembered per
+//! repo in `offline-queue.json` next to the settings until they are replayed.
+
+use crate::settings::config_dir;
+use anyhow::Result;
+use backend_api as api;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Pending operations of every repo, oldest first. Without a path nothing is persisted.
+#[derive(Debug, Default)]
+pub struct OfflineQueue {
+    path: Option<PathBuf>,
+    repos: HashMap<String, Vec<api::PendingOperation>>,
+}
+
+impl OfflineQueue {
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(config_dir()?.join("offline-queue.json"))
+    }
+
+    /// Loads `path`, starting empty if it is missing or broken.
+    pub fn load(path: PathBuf) -> Self {
+        let repos = match std::fs::read_to_string(&path) {
+            Err(_) => HashMap::new(),
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                println!("ignoring invalid {:?}, {}", path, e);
+                HashMap::new()
+            }),
+        };
+        Self {
+            path: Some(path),
+            repos,
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, serde_json::to_string_pretty(&self.repos)?)?;
+        }
+        Ok(())
+    }
+
+    pub fn pending(&self, repo: &str) -> Vec<api::PendingOperation> {
+        self.repos.get(repo).cloned().unwrap_or_default()
+    }
+
+    /// Adds `operation` after the others. An unlock of a file whose lock is still
+    /// pending cancels both, as does the reverse.
+    pub fn push(&mut self, repo: &str, operation: api::PendingOperation) -> Result<()> {
+        let pending = self.repos.entry(repo.to_string()).or_default();
+        let before = pending.len();
+        pending.retain(|o| o.path != operation.path || o.lock == operation.lock);
+        if pending.len() == before {
+            pending.retain(|o| o.path != operation.path);
+            pending.push(operation);
+        }
+        self.save()
+    }
+
+    /// Drops `operation` once it is done, leaving any asked for since on the same path.
+    pub fn remove(&mut self, repo: &str, operation: &api::PendingOperation) -> Result<()> {
+        if let Some(pending) = self.repos.get_mut(repo) {
+            pending.retain(|o| o != operation);
+        }
+        self.save()
+    }
+
+    /// Drops the pending operation on `path`.
+    pub fn cancel(&mut self, repo: &str, path: &str) -> Result<()> {
+        if let Some(pending) = self.repos.get_mut(repo) {
+            pending.retain(|o| o.path != path);
+        }
+        self.save()
+    }
+}
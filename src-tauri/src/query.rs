@@ -0,0 +1,228 @@
+//! The search box query language. Filters like `owner:alice`, `locked:mine`,
+//! `ext:uasset`, `in:Content/Characters`, `age:>7d` and `status:modified` can be
+//! mixed with free text, which is fuzzy matched against the path. Filter values may
+//! be quoted and list alternatives separated by commas, e.g. `ext:uasset,umap`.
+
+use anyhow::{anyhow, Result};
+use backend_api::LockEntry;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+
+const FILTERS: &str = "owner, locked, ext, in, age, status";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Locked {
+    Yes,
+    No,
+    Mine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Age {
+    OlderThan(Duration),
+    NewerThan(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Modified,
+    Clean,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Owner(Vec<String>),
+    Locked(Locked),
+    Ext(Vec<String>),
+    In(Vec<String>),
+    Age(Age),
+    Status(Status),
+}
+
+/// What filters are checked against besides the path.
+pub struct Context<'a> {
+    /// Current locks by path.
+    pub locks: HashMap<&'a str, &'a LockEntry>,
+    /// Ids of our own locks, only needed for `locked:mine`.
+    pub own_locks: Option<&'a HashSet<String>>,
+    /// Modified files, only needed for `status:`.
+    pub modified: Option<&'a HashSet<String>>,
+    pub now: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Query {
+    /// Free text for the fuzzy matcher.
+    pub text: String,
+    pub filters: Vec<Filter>,
+}
+
+/// Splits on whitespace outside of double quotes, dropping the quotes.
+fn tokenize(input: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow!("invalid query, unterminated quote"));
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .filter(|v| !v.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// `None` for unknown units and for counts too large to be a duration.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let unit = value.chars().last()?;
+    let count: u64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let duration = std::time::Duration::from_secs(count.checked_mul(seconds)?);
+    Duration::from_std(duration).ok()
+}
+
+fn parse_age(value: &str) -> Option<Age> {
+    if let Some(v) = value.strip_prefix('<') {
+        let v = v.strip_prefix('=').unwrap_or(v);
+        return parse_duration(v).map(Age::NewerThan);
+    }
+    let v = value.strip_prefix('>').unwrap_or(value);
+    let v = v.strip_prefix('=').unwrap_or(v);
+    parse_duration(v).map(Age::OlderThan)
+}
+
+fn is_filter_key(key: &str) -> bool {
+    key.len() > 1 && key.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn parse_filter(key: &str, value: &str) -> Result<Filter> {
+    let invalid =
+        |expected: &str| anyhow!("invalid query, `{}:{}` expects {}", key, value, expected);
+    let filter = match key {
+        "owner" => Filter::Owner(list(value)),
+        "locked" => match value.to_lowercase().as_str() {
+            "yes" | "true" => Filter::Locked(Locked::Yes),
+            "no" | "false" => Filter::Locked(Locked::No),
+            "mine" => Filter::Locked(Locked::Mine),
+            _ => return Err(invalid("yes, no or mine")),
+        },
+        "ext" => Filter::Ext(list(value.trim_start_matches('.'))),
+        "in" => Filter::In(list(&value.replace('\\', "/"))),
+        "age" => {
+            let age = parse_age(value).ok_or_else(|| invalid("an age like >7d or <12h"))?;
+            Filter::Age(age)
+        }
+        "status" => match value.to_lowercase().as_str() {
+            "modified" => Filter::Status(Status::Modified),
+            "clean" => Filter::Status(Status::Clean),
+            _ => return Err(invalid("modified or clean")),
+        },
+        _ => {
+            return Err(anyhow!(
+                "invalid query, unknown filter `{}:`, expected one of {}",
+                key,
+                FILTERS
+            ))
+        }
+    };
+    Ok(filter)
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut query = Query::default();
+        let mut text = Vec::new();
+        for token in tokenize(input)? {
+            // anything but `word:` is free text, so `C:/Projects` still searches
+            let filter = token.split_once(':').filter(|(key, _)| is_filter_key(key));
+            match filter {
+                None => text.push(token),
+                Some((_, "")) => {
+                    return Err(anyhow!("invalid query, `{}` needs a value", token));
+                }
+                Some((key, value)) => query
+                    .filters
+                    .push(parse_filter(&key.to_lowercase(), value)?),
+            }
+        }
+        query.text = text.join(" ");
+        Ok(query)
+    }
+
+    pub fn needs_own_locks(&self) -> bool {
+        self.filters
+            .iter()
+            .any(|f| *f == Filter::Locked(Locked::Mine))
+    }
+
+    pub fn needs_status(&self) -> bool {
+        self.filters.iter().any(|f| matches!(f, Filter::Status(_)))
+    }
+
+    /// Whether `path` passes every filter, the free text is left to the matcher.
+    pub fn matches(&self, path: &str, context: &Context) -> bool {
+        let lock = context.locks.get(path);
+        self.filters.iter().all(|filter| match filter {
+            Filter::Owner(owners) => {
+                lock.map_or(false, |l| owners.contains(&l.owner.name.to_lowercase()))
+            }
+            Filter::Locked(Locked::Yes) => lock.is_some(),
+            Filter::Locked(Locked::No) => lock.is_none(),
+            Filter::Locked(Locked::Mine) => lock.map_or(false, |l| {
+                context.own_locks.map_or(false, |own| own.contains(&l.id))
+            }),
+            Filter::Ext(extensions) => {
+                let name = path.rsplit('/').next().unwrap_or(path);
+                name.rsplit_once('.')
+                    .map_or(false, |(_, ext)| extensions.contains(&ext.to_lowercase()))
+            }
+            Filter::In(dirs) => {
+                let path = path.to_lowercase();
+                dirs.iter().any(|d| {
+                    let d = d.trim_matches('/');
+                    d.is_empty() || path.starts_with(&format!("{}/", d))
+                })
+            }
+            Filter::Age(age) => lock
+                .and_then(|l| DateTime::parse_from_rfc3339(&l.locked_at).ok())
+                .map_or(false, |locked_at| {
+                    let elapsed = context.now.signed_duration_since(locked_at);
+                    match age {
+                        Age::OlderThan(d) => elapsed > *d,
+                        Age::NewerThan(d) => elapsed < *d,
+                    }
+                }),
+            Filter::Status(status) => {
+                let modified = context.modified.map_or(false, |m| m.contains(path));
+                match status {
+                    Status::Modified => modified,
+                    Status::Clean => !modified,
+                }
+            }
+        })
+    }
+}
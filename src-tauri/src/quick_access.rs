@@ -0,0 +1,99 @@
+//! Pinned, watched, queued and recently locked or unlocked files, remembered per repo in
+//! `quick-access.json` next to the settings.
+
+use crate::settings::config_dir;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// How many recently used files are kept per repo.
+pub const RECENT_LIMIT: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RepoFiles {
+    pub pinned: Vec<String>,
+    /// Most recent first.
+    pub recent: Vec<String>,
+    /// Files whose lock changes are notified.
+    pub watched: Vec<String>,
+    /// Files to lock as soon as they are unlocked, oldest first.
+    pub queued: Vec<String>,
+}
+
+/// Quick access lists of every repo. Without a path nothing is persisted.
+#[derive(Debug, Default)]
+pub struct QuickAccess {
+    path: Option<PathBuf>,
+    repos: HashMap<String, RepoFiles>,
+}
+
+impl QuickAccess {
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(config_dir()?.join("quick-access.json"))
+    }
+
+    /// Loads `path`, starting empty if it is missing or broken.
+    pub fn load(path: PathBuf) -> Self {
+        let repos = match std::fs::read_to_string(&path) {
+            Err(_) => HashMap::new(),
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                println!("ignoring invalid {:?}, {}", path, e);
+                HashMap::new()
+            }),
+        };
+        Self {
+            path: Some(path),
+            repos,
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, serde_json::to_string_pretty(&self.repos)?)?;
+        }
+        Ok(())
+    }
+
+    pub fn files(&self, repo: &str) -> RepoFiles {
+        self.repos.get(repo).cloned().unwrap_or_default()
+    }
+
+    pub fn pin(&mut self, repo: &str, file: &str, pinned: bool) -> Result<()> {
+        let files = self.repos.entry(repo.to_string()).or_default();
+        files.pinned.retain(|f| f != file);
+        if pinned {
+            files.pinned.push(file.to_string());
+        }
+        self.save()
+    }
+
+    pub fn watch(&mut self, repo: &str, file: &str, watched: bool) -> Result<()> {
+        let files = self.repos.entry(repo.to_string()).or_default();
+        files.watched.retain(|f| f != file);
+        if watched {
+            files.watched.push(file.to_string());
+        }
+        self.save()
+    }
+
+    pub fn queue(&mut self, repo: &str, file: &str, queued: bool) -> Result<()> {
+        let files = self.repos.entry(repo.to_string()).or_default();
+        files.queued.retain(|f| f != file);
+        if queued {
+            files.queued.push(file.to_string());
+        }
+        self.save()
+    }
+
+    /// Moves `file` to the front of the recently used files.
+    pub fn touch(&mut self, repo: &str, file: &str) -> Result<()> {
+        let files = self.repos.entry(repo.to_string()).or_default();
+        files.recent.retain(|f| f != file);
+        files.recent.insert(0, file.to_string());
+        files.recent.truncate(RECENT_LIMIT);
+        self.save()
+    }
+}
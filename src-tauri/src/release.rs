@@ -0,0 +1,128 @@
+//! Releasing our locks once the changes to them are pushed. A lock counts as pushed
+//! when a commit of ours on the upstream branch changed the file after it was locked
+//! and no unpushed commit or uncommitted change touches it anymore.
+
+use crate::git;
+use crate::runner::CommandRunner;
+use anyhow::{anyhow, Result};
+use backend_api::LockEntry;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the git hook the app installs, git runs it after refs changed. Git has no
+/// hook after a push, so the script tells pushes from fetches by where the remote
+/// tracking refs move to.
+pub const HOOK: &str = "reference-transaction";
+const HOOK_MARKER: &str = "# installed by git-lock-manager";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseMode {
+    /// Pushed locks are kept.
+    Off,
+    /// The app asks before unlocking pushed locks.
+    Prompt,
+    /// Pushed locks are unlocked in the background.
+    Auto,
+}
+
+impl Default for ReleaseMode {
+    fn default() -> Self {
+        ReleaseMode::Prompt
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ReleaseSettings {
+    pub mode: ReleaseMode,
+}
+
+fn locked_at(lock: &LockEntry) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&lock.locked_at)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// The `locks`, expected to be our own, whose changes are pushed.
+pub fn pushed_locks(
+    runner: &dyn CommandRunner,
+    repo: &Path,
+    locks: &[LockEntry],
+) -> Result<Vec<LockEntry>> {
+    let since = match locks.iter().filter_map(locked_at).min() {
+        Some(since) => since,
+        None => return Ok(Vec::new()),
+    };
+    if git::get_upstream(runner, repo)?.is_none() {
+        return Ok(Vec::new());
+    }
+    // commits fetched from others don't release anything, without an identity no
+    // commit can be told to be ours
+    let email = match git::get_user_email(runner, repo)? {
+        Some(email) => email,
+        None => return Ok(Vec::new()),
+    };
+    let pushed = git::get_pushed_changes(runner, repo, &since.to_rfc3339(), &email)?;
+    let unpushed = git::get_unpushed_files(runner, repo)?;
+    let modified = git::get_modified_files(runner, repo)?;
+    Ok(locks
+        .iter()
+        .filter(|l| !unpushed.contains(&l.path) && !modified.contains(&l.path))
+        .filter(|l| {
+            let changed = pushed
+                .get(&l.path)
+                .and_then(|d| DateTime::parse_from_rfc3339(d).ok());
+            match (changed, locked_at(l)) {
+                (Some(changed), Some(locked)) => changed >= locked,
+                _ => false,
+            }
+        })
+        .cloned()
+        .collect())
+}
+
+fn hook_script(exe: &Path) -> String {
+    // git for windows runs hooks with its own sh, which wants forward slashes
+    let exe = exe.to_string_lossy().replace('\\', "/");
+    format!(
+        "#!/bin/sh\n\
+         {}\n\
+         # releases pushed locks whenever a push moves the remote tracking refs, which\n\
+         # then point at commits our branches have while a fetch brings new ones\n\
+         [ \"$1\" = committed ] || exit 0\n\
+         pushed=\n\
+         while read -r old new ref; do\n\
+         \tcase \"$ref\" in refs/remotes/*)\n\
+         \t\t[ -n \"$(git for-each-ref --contains \"$new\" refs/heads 2>/dev/null)\" ] && pushed=1 ;;\n\
+         \tesac\n\
+         done\n\
+         [ -n \"$pushed\" ] || exit 0\n\
+         \"{}\" --release-pushed </dev/null >/dev/null 2>&1 &\n",
+        HOOK_MARKER, exe
+    )
+}
+
+/// Installs the hook running `exe --release-pushed` after pushes. An existing hook
+/// that wasn't installed by the app is left alone.
+pub fn install_hook(runner: &dyn CommandRunner, repo: &Path, exe: &Path) -> Result<PathBuf> {
+    let path = git::get_hook_path(runner, repo, HOOK)?;
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        if !existing.contains(HOOK_MARKER) {
+            return Err(anyhow!(
+                "{:?} already exists, add `{:?} --release-pushed` to it by hand",
+                path,
+                exe
+            ));
+        }
+    }
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(&path, hook_script(exe))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(path)
+}
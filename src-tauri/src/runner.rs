@@ -0,0 +1,204 @@
+//! Every git invocation of the backend goes through a [`CommandRunner`], so the
+//! executable, environment and working directory can be configured per repo, and
+//! tests can swap in a [`RecordingRunner`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A git invocation, `git -C <dir> <args>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitCommand {
+    pub dir: PathBuf,
+    /// Runs this instead of git, in `dir` and without `-C`, e.g. `ssh` for the
+    /// `git-lfs-authenticate` of an ssh remote.
+    pub program: Option<String>,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub stdin: Option<String>,
+}
+
+impl GitCommand {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            program: None,
+            args: Vec::new(),
+            env: Vec::new(),
+            stdin: None,
+        }
+    }
+
+    pub fn program<S: Into<String>>(mut self, program: S) -> Self {
+        self.program = Some(program.into());
+        self
+    }
+
+    pub fn arg<S: Into<String>>(mut self, arg: S) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I: IntoIterator<Item = S>, S: Into<String>>(mut self, args: I) -> Self {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn env<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn stdin<S: Into<String>>(mut self, stdin: S) -> Self {
+        self.stdin = Some(stdin.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+pub trait CommandRunner: Send + Sync {
+    fn run(&self, command: &GitCommand) -> io::Result<CommandOutput>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RepoOverride {
+    /// Directory git runs in instead of the picked repo, e.g. the repo path
+    /// inside a container.
+    pub work_dir: Option<PathBuf>,
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RunnerSettings {
+    /// Git executable, `git` from `PATH` when unset.
+    pub executable: Option<PathBuf>,
+    /// Extra environment for every git process.
+    pub env: BTreeMap<String, String>,
+    /// Command prefix git is run through, e.g. `["ssh", "build-box"]` or
+    /// `["docker", "exec", "builder"]`. The environment is only applied to the
+    /// local process, wrappers have to forward it themselves.
+    pub wrapper: Vec<String>,
+    /// Overrides keyed by repo path.
+    pub repos: HashMap<String, RepoOverride>,
+}
+
+/// Spawns real processes according to [`RunnerSettings`].
+#[derive(Default)]
+pub struct ProcessRunner {
+    settings: RunnerSettings,
+}
+
+impl ProcessRunner {
+    pub fn new(settings: RunnerSettings) -> Self {
+        Self { settings }
+    }
+}
+
+impl CommandRunner for ProcessRunner {
+    fn run(&self, command: &GitCommand) -> io::Result<CommandOutput> {
+        let repo_override = self
+            .settings
+            .repos
+            .get(command.dir.to_str().unwrap_or_default());
+        let dir = repo_override
+            .and_then(|o| o.work_dir.clone())
+            .unwrap_or_else(|| command.dir.clone());
+        let git = match &command.program {
+            Some(program) => PathBuf::from(program),
+            None => self
+                .settings
+                .executable
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("git")),
+        };
+
+        let mut process = match self.settings.wrapper.split_first() {
+            None => std::process::Command::new(git),
+            Some((program, wrapper_args)) => {
+                let mut process = std::process::Command::new(program);
+                process.args(wrapper_args).arg(git);
+                process
+            }
+        };
+        if command.program.is_none() {
+            process.arg("-C").arg(&dir);
+        }
+        process.args(&command.args);
+        if self.settings.wrapper.is_empty() {
+            process.current_dir(&dir);
+        }
+        process.envs(&self.settings.env);
+        if let Some(o) = repo_override {
+            process.envs(&o.env);
+        }
+        process.envs(command.env.iter().map(|(k, v)| (k, v)));
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            process.creation_flags(winapi::um::winbase::CREATE_NO_WINDOW);
+        }
+
+        let output = match &command.stdin {
+            None => process.output()?,
+            Some(input) => {
+                process
+                    .stdin(std::process::Stdio::piped())
+                    .stdout(std::process::Stdio::piped())
+                    .stderr(std::process::Stdio::piped());
+                let mut child = process.spawn()?;
+                let mut stdin = child.stdin.take().unwrap();
+                let input = input.clone();
+                // written from another thread so a full stdout pipe can't deadlock us
+                let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+                let output = child.wait_with_output()?;
+                // a command exiting without reading all of its input fails in its output
+                let _ = writer.join();
+                output
+            }
+        };
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// Records every command and answers with `reply` instead of running git.
+pub struct RecordingRunner {
+    calls: Mutex<Vec<GitCommand>>,
+    reply: Box<dyn Fn(&GitCommand) -> io::Result<CommandOutput> + Send + Sync>,
+}
+
+impl RecordingRunner {
+    pub fn new<F>(reply: F) -> Self
+    where
+        F: Fn(&GitCommand) -> io::Result<CommandOutput> + Send + Sync + 'static,
+    {
+        Self {
+            calls: Mutex::new(Vec::new()),
+            reply: Box::new(reply),
+        }
+    }
+
+    pub fn calls(&self) -> Vec<GitCommand> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn run(&self, command: &GitCommand) -> io::Result<CommandOutput> {
+        self.calls.lock().unwrap().push(command.clone());
+        (self.reply)(command)
+    }
+}
@@ -0,0 +1,82 @@
+use crate::git;
+use crate::runner::CommandRunner;
+use anyhow::Result;
+use backend_api::SearchResult;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// All tracked files of a repo and which of them are lfs objects or lockable.
+pub struct FileIndex {
+    pub tracked: Vec<String>,
+    pub lfs: HashSet<String>,
+    pub lockable: HashSet<String>,
+}
+
+impl FileIndex {
+    pub fn build(runner: &dyn CommandRunner, repo: &Path, lfs_files: &[String]) -> Result<Self> {
+        let tracked = git::get_tracked_files(runner, repo)?;
+        let lockable = git::get_lockable_files(runner, repo, &tracked)?;
+        Ok(Self {
+            tracked,
+            lfs: lfs_files.iter().cloned().collect(),
+            lockable,
+        })
+    }
+
+    pub fn tag(&self, result: &mut SearchResult) {
+        result.lfs = self.lfs.contains(&result.path);
+        result.lockable = self.lockable.contains(&result.path);
+    }
+}
+
+/// Whether every matched char lies in the file name rather than the directories.
+fn matches_file_name(path: &str, indices: &[usize]) -> bool {
+    let name_start = path
+        .rfind('/')
+        .map(|i| path[..=i].chars().count())
+        .unwrap_or(0);
+    indices.iter().all(|&i| i >= name_start)
+}
+
+/// Fuzzy matches `files` against `filter`, best match first. Equal scores prefer
+/// matches inside the file name, then shorter paths. An empty filter keeps every
+/// file, sorted by path.
+pub fn rank(matcher: &SkimMatcherV2, files: &[String], filter: &str) -> Vec<SearchResult> {
+    let mut results: Vec<(SearchResult, bool)> = files
+        .iter()
+        .filter_map(|f| {
+            let (score, indices) = matcher.fuzzy_indices(f, filter)?;
+            let in_file_name = matches_file_name(f, &indices);
+            Some((
+                SearchResult {
+                    path: f.clone(),
+                    score,
+                    indices,
+                    lfs: false,
+                    lockable: false,
+                    lock: None,
+                },
+                in_file_name,
+            ))
+        })
+        .collect();
+    if filter.is_empty() {
+        results.sort_by(|(a, _), (b, _)| a.path.cmp(&b.path));
+        return results.into_iter().map(|(r, _)| r).collect();
+    }
+    results.sort_by(|(a, a_name), (b, b_name)| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| match (a_name, b_name) {
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                _ => Ordering::Equal,
+            })
+            .then_with(|| a.path.len().cmp(&b.path.len()))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    results.into_iter().map(|(r, _)| r).collect()
+}
@@ -0,0 +1,53 @@
+use crate::lfs_api::LfsSettings;
+use crate::network::NetworkSettings;
+use crate::release::ReleaseSettings;
+use crate::runner::RunnerSettings;
+use crate::stats::StatsSettings;
+use crate::watch::WatchSettings;
+use anyhow::{anyhow, Result};
+use backend_api::Command;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Per user directory for settings and other app files.
+pub fn config_dir() -> Result<PathBuf> {
+    let dir = dirs::config_dir().ok_or(anyhow!("no config directory for current user"))?;
+    Ok(dir.join("git-lock-manager"))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub git: RunnerSettings,
+    pub watch: WatchSettings,
+    pub release: ReleaseSettings,
+    pub stats: StatsSettings,
+    pub lfs: LfsSettings,
+    pub network: NetworkSettings,
+    /// Key bindings replacing the defaults, an empty string unbinds a command.
+    pub keys: HashMap<Command, String>,
+    /// Locale of the interface like `de` or `zh-CN`, empty for the system's.
+    pub locale: String,
+}
+
+impl Settings {
+    fn path() -> Result<PathBuf> {
+        Ok(config_dir()?.join("settings.json"))
+    }
+
+    /// Loads `settings.json`, falling back to defaults if it is missing or broken.
+    pub fn load() -> Self {
+        let path = match Self::path() {
+            Ok(p) => p,
+            Err(_) => return Self::default(),
+        };
+        match std::fs::read_to_string(&path) {
+            Err(_) => Self::default(),
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                println!("ignoring invalid {:?}, {}", path, e);
+                Self::default()
+            }),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! The last lock list fetched for each repo, remembered in `lock-snapshots.json` next
+//! to the settings and shown while the lfs server can't be reached.
+
+use crate::settings::config_dir;
+use anyhow::Result;
+use backend_api as api;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// When the locks were fetched, RFC 3339.
+    pub time: String,
+    pub locks: Vec<api::LockEntry>,
+}
+
+/// Snapshots of every repo. Without a path nothing is persisted.
+#[derive(Debug, Default)]
+pub struct Snapshots {
+    path: Option<PathBuf>,
+    repos: HashMap<String, Snapshot>,
+}
+
+impl Snapshots {
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(config_dir()?.join("lock-snapshots.json"))
+    }
+
+    /// Loads `path`, starting empty if it is missing or broken.
+    pub fn load(path: PathBuf) -> Self {
+        let repos = match std::fs::read_to_string(&path) {
+            Err(_) => HashMap::new(),
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                println!("ignoring invalid {:?}, {}", path, e);
+                HashMap::new()
+            }),
+        };
+        Self {
+            path: Some(path),
+            repos,
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, serde_json::to_string_pretty(&self.repos)?)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, repo: &str) -> Option<Snapshot> {
+        self.repos.get(repo).cloned()
+    }
+
+    /// Remembers `locks` as fetched now.
+    pub fn set(&mut self, repo: &str, locks: &[api::LockEntry]) -> Result<()> {
+        let snapshot = Snapshot {
+            time: chrono::Utc::now().to_rfc3339(),
+            locks: locks.to_vec(),
+        };
+        self.repos.insert(repo.to_string(), snapshot);
+        self.save()
+    }
+}
@@ -0,0 +1,114 @@
+//! Lock statistics of a team, shown on the dashboard and printed by `--stats`.
+
+use crate::export::{age, format_duration};
+use backend_api::{LockEntry, LockStats};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Directory levels locks are grouped by, e.g. `Content/Characters`.
+pub const DIRECTORY_DEPTH: usize = 2;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StatsSettings {
+    /// Locks older than this many days are listed as stale.
+    pub stale_days: u32,
+}
+
+impl Default for StatsSettings {
+    fn default() -> Self {
+        Self { stale_days: 7 }
+    }
+}
+
+/// The directory of `path` cut to `DIRECTORY_DEPTH` levels, "" at the root.
+fn directory(path: &str) -> String {
+    let dirs: Vec<&str> = path.split('/').collect();
+    let depth = (dirs.len() - 1).min(DIRECTORY_DEPTH);
+    dirs[..depth].join("/")
+}
+
+/// Counts by key, most first, ties by key.
+fn counts(keys: impl Iterator<Item = String>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    keys.for_each(|k| *counts.entry(k).or_default() += 1);
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts
+}
+
+pub fn compute(locks: &[LockEntry], now: DateTime<Utc>, stale_days: u32) -> LockStats {
+    let mut aged: Vec<(&LockEntry, i64)> = locks
+        .iter()
+        .filter_map(|l| {
+            let locked_at = DateTime::parse_from_rfc3339(&l.locked_at).ok()?;
+            Some((l, (now - locked_at.with_timezone(&Utc)).num_seconds()))
+        })
+        .collect();
+    aged.sort_by(|(_, a), (_, b)| b.cmp(a));
+    let median_age = match aged.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(aged[n / 2].1),
+        n => Some((aged[n / 2 - 1].1 + aged[n / 2].1) / 2),
+    };
+    let stale_after = i64::from(stale_days) * 86400;
+    LockStats {
+        total: locks.len(),
+        per_owner: counts(locks.iter().map(|l| l.owner.name.clone())),
+        per_directory: counts(locks.iter().map(|l| directory(&l.path))),
+        median_age,
+        oldest: aged.first().map(|(l, _)| (*l).clone()),
+        longest_age: aged.first().map(|(_, age)| *age),
+        stale_days,
+        stale: aged
+            .iter()
+            .take_while(|(_, age)| *age > stale_after)
+            .map(|(l, _)| (*l).clone())
+            .collect(),
+    }
+}
+
+/// `stats` as plain text for the terminal.
+pub fn summary(stats: &LockStats, now: DateTime<Utc>) -> String {
+    let mut out = format!("{} locks\n", stats.total);
+    if let (Some(median), Some(longest), Some(oldest)) =
+        (stats.median_age, stats.longest_age, &stats.oldest)
+    {
+        out.push_str(&format!("median age {}\n", format_duration(median)));
+        out.push_str(&format!(
+            "longest {} by {}, {}\n",
+            format_duration(longest),
+            oldest.owner.name,
+            oldest.path
+        ));
+    }
+    let table = |out: &mut String, title: &str, counts: &[(String, usize)]| {
+        out.push_str(&format!("\n{}\n", title));
+        let width = counts
+            .iter()
+            .map(|(k, _)| k.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (key, count) in counts {
+            let key = if key.is_empty() { "/" } else { key };
+            out.push_str(&format!("  {:<width$}  {}\n", key, count, width = width));
+        }
+    };
+    table(&mut out, "per owner", &stats.per_owner);
+    table(&mut out, "per directory", &stats.per_directory);
+    out.push_str(&format!(
+        "\n{} older than {} days\n",
+        stats.stale.len(),
+        stats.stale_days
+    ));
+    for lock in &stats.stale {
+        out.push_str(&format!(
+            "  {}  {}  {}\n",
+            age(&lock.locked_at, now),
+            lock.owner.name,
+            lock.path
+        ));
+    }
+    out
+}
@@ -0,0 +1,74 @@
+//! Directory view of the lfs file list, one level at a time.
+
+use backend_api::{LockEntry, TreeNode};
+use std::collections::{BTreeMap, HashMap};
+
+/// Prefix of the paths below `dir`, empty for the repo root.
+fn prefix(dir: &str) -> String {
+    let dir = dir.trim_matches('/');
+    match dir.is_empty() {
+        true => String::new(),
+        false => format!("{}/", dir),
+    }
+}
+
+/// Whether `path` is somewhere below `dir`.
+pub fn contains(dir: &str, path: &str) -> bool {
+    path.starts_with(&prefix(dir))
+}
+
+/// Files somewhere below `dir`.
+pub fn files_in<'a>(files: &'a [String], dir: &str) -> impl Iterator<Item = &'a String> {
+    let prefix = prefix(dir);
+    files.iter().filter(move |f| f.starts_with(&prefix))
+}
+
+/// The folders and files directly in `dir`, folders first, with lock counts of
+/// everything below them.
+pub fn children(files: &[String], locks: &[LockEntry], dir: &str) -> Vec<TreeNode> {
+    let prefix = prefix(dir);
+    let locks: HashMap<&str, &LockEntry> = locks.iter().map(|l| (l.path.as_str(), l)).collect();
+    let mut nodes: BTreeMap<(bool, String), TreeNode> = BTreeMap::new();
+    let mut owners: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    for file in files_in(files, dir) {
+        let rest = &file[prefix.len()..];
+        let (name, is_dir) = match rest.find('/') {
+            Some(i) => (&rest[..i], true),
+            None => (rest, false),
+        };
+        let node = nodes
+            .entry((!is_dir, name.to_string()))
+            .or_insert_with(|| TreeNode {
+                name: name.to_string(),
+                path: format!("{}{}", prefix, name),
+                is_dir,
+                files: 0,
+                locked: 0,
+                owners: Vec::new(),
+                lock: None,
+            });
+        node.files += 1;
+        if let Some(&lock) = locks.get(file.as_str()) {
+            node.locked += 1;
+            *owners
+                .entry(node.path.clone())
+                .or_default()
+                .entry(lock.owner.name.clone())
+                .or_default() += 1;
+            if !is_dir {
+                node.lock = Some(lock.clone());
+            }
+        }
+    }
+    nodes
+        .into_iter()
+        .map(|(_, mut node)| {
+            if let Some(counts) = owners.remove(&node.path) {
+                node.owners = counts.into_iter().collect();
+                node.owners
+                    .sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+            }
+            node
+        })
+        .collect()
+}
@@ -0,0 +1,109 @@
+//! Watched files: every lock refresh is compared with the previous one and changes
+//! to watched files end up in the inbox and as desktop notifications. Queued files
+//! are locked by the refresh once they are free.
+
+use crate::handler::State;
+use crate::release::ReleaseMode;
+use backend_api::{Event, LockEntry, WatchEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How many events the inbox keeps.
+pub const INBOX_LIMIT: usize = 100;
+
+/// Shows watch events outside of the app.
+pub trait Notifier: Send + Sync {
+    fn notify(&self, event: &WatchEvent);
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WatchSettings {
+    /// Seconds between lock refreshes while something is watched, 0 turns polling off.
+    pub interval_secs: u64,
+}
+
+impl Default for WatchSettings {
+    fn default() -> Self {
+        Self { interval_secs: 60 }
+    }
+}
+
+/// Remembers the locks of the last refresh.
+#[derive(Debug, Default)]
+pub struct Watcher {
+    last: Option<HashMap<String, LockEntry>>,
+}
+
+impl Watcher {
+    /// Forgets the last locks, e.g. after another repo is picked.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Changes to `watched` files since the last call. The first call only records
+    /// the locks.
+    pub fn update(&mut self, watched: &[String], locks: &[LockEntry]) -> Vec<WatchEvent> {
+        let current: HashMap<String, LockEntry> =
+            locks.iter().map(|l| (l.path.clone(), l.clone())).collect();
+        let last = match self.last.replace(current) {
+            Some(last) => last,
+            None => return Vec::new(),
+        };
+        let current = self.last.as_ref().unwrap();
+        let time = chrono::Utc::now().to_rfc3339();
+        let event = |lock: &LockEntry, locked: bool| WatchEvent {
+            path: lock.path.clone(),
+            owner: lock.owner.name.clone(),
+            locked,
+            automatic: false,
+            time: time.clone(),
+        };
+        let mut events = Vec::new();
+        for path in watched {
+            match (last.get(path), current.get(path)) {
+                (None, Some(now)) => events.push(event(now, true)),
+                (Some(before), None) => events.push(event(before, false)),
+                (Some(before), Some(now)) if before.id != now.id => {
+                    events.push(event(before, false));
+                    events.push(event(now, true));
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// Refreshes the locks every `interval` while the current repo has watched or queued
+/// files or operations waiting for the server, and releases pushed locks if that is
+/// automatic.
+pub fn spawn_poller(state: State, interval: Duration) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        let repo = state.repo.read().unwrap().clone();
+        if repo.is_empty() {
+            continue;
+        }
+        if state.release_mode == ReleaseMode::Auto {
+            // refreshes the locks as well
+            if let Err(e) = state.release_pushed(ReleaseMode::Auto) {
+                let message = format!("failed to release pushed locks, {}", e);
+                println!("{}", message);
+                state.events.emit(Event::Error { message });
+            }
+            continue;
+        }
+        let files = state.quick_access.lock().unwrap().files(&repo);
+        let pending = state.offline_queue.lock().unwrap().pending(&repo);
+        if files.watched.is_empty() && files.queued.is_empty() && pending.is_empty() {
+            continue;
+        }
+        if let Err(e) = state.refresh_locks() {
+            let message = format!("failed to refresh watched and queued locks, {}", e);
+            println!("{}", message);
+            state.events.emit(Event::Error { message });
+        }
+    });
+}
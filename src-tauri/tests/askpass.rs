@@ -0,0 +1,83 @@
+use backend_api::Event;
+use git_lock_manager::askpass::{self, Askpass};
+use git_lock_manager::events::{EventBus, EventSink};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+struct ChannelSink(Mutex<Sender<Event>>);
+
+impl EventSink for ChannelSink {
+    fn emit(&self, event: &Event) {
+        let _ = self.0.lock().unwrap().send(event.clone());
+    }
+}
+
+fn start(timeout: Duration) -> (Arc<Askpass>, String, Receiver<Event>) {
+    let events = Arc::new(EventBus::default());
+    let (sender, receiver) = mpsc::channel();
+    events.subscribe(Arc::new(ChannelSink(Mutex::new(sender))));
+    let askpass = Askpass::start(events, timeout).unwrap();
+    let env = askpass.env(Path::new("/opt/git-lock-manager"));
+    assert!(env.contains(&("GIT_TERMINAL_PROMPT".to_string(), "0".to_string())));
+    assert!(env.contains(&(
+        "GIT_ASKPASS".to_string(),
+        "/opt/git-lock-manager".to_string()
+    )));
+    let connect = env
+        .into_iter()
+        .find(|(k, _)| k == askpass::ENV)
+        .map(|(_, v)| v)
+        .unwrap();
+    (askpass, connect, receiver)
+}
+
+#[test]
+fn prompts_are_answered_by_the_user() {
+    let (askpass, connect, events) = start(askpass::TIMEOUT);
+    let helper = std::thread::spawn(move || {
+        askpass::ask_app(&connect, "Password for 'https://alice@example.com': ").unwrap()
+    });
+
+    let id = match events.recv_timeout(Duration::from_secs(5)).unwrap() {
+        Event::CredentialPrompt { id, prompt, secret } => {
+            assert_eq!(prompt, "Password for 'https://alice@example.com': ");
+            assert!(secret);
+            id
+        }
+        e => panic!("unexpected event {:?}", e),
+    };
+    askpass.answer(id, Some("secret".to_string())).unwrap();
+
+    assert_eq!(helper.join().unwrap(), Some("secret".to_string()));
+    match events.recv_timeout(Duration::from_secs(5)).unwrap() {
+        Event::PromptClosed { id: closed } => assert_eq!(closed, id),
+        e => panic!("unexpected event {:?}", e),
+    }
+    assert!(askpass.answer(id, None).is_err());
+}
+
+#[test]
+fn unanswered_prompts_are_cancelled() {
+    let (_askpass, connect, events) = start(Duration::from_millis(100));
+
+    let answer = askpass::ask_app(&connect, "Username for 'https://example.com': ").unwrap();
+
+    assert_eq!(answer, None);
+    let events: Vec<Event> = events.try_iter().collect();
+    assert!(matches!(
+        events[0],
+        Event::CredentialPrompt { secret: false, .. }
+    ));
+    assert!(matches!(events[1], Event::PromptClosed { .. }));
+}
+
+#[test]
+fn helpers_need_the_token() {
+    let (_askpass, connect, events) = start(askpass::TIMEOUT);
+    let addr = connect.split(' ').next().unwrap();
+
+    assert!(askpass::ask_app(&format!("{} guess", addr), "Password: ").is_err());
+    assert!(events.try_recv().is_err());
+}
@@ -0,0 +1,109 @@
+//! Helpers shared by the integration tests, each of which uses only some of them.
+#![allow(dead_code)]
+
+use backend_api::{Event, LockEntry, Owner, WatchEvent};
+use git_lock_manager::events::EventSink;
+use git_lock_manager::handler::State;
+use git_lock_manager::runner::ProcessRunner;
+use git_lock_manager::watch::Notifier;
+use mock_lfs_server::{Config, MockServer};
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+
+pub fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("failed to run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+pub fn new_repo() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    dir
+}
+
+/// A new repository whose lock api is `server`.
+pub fn server_repo(server: &MockServer) -> TempDir {
+    let dir = new_repo();
+    git(dir.path(), &["config", "lfs.url", &server.url()]);
+    dir
+}
+
+/// A lock server accepting `alice` with `secret` and `bob` with `hunter2`.
+pub fn start_server() -> MockServer {
+    MockServer::start(Config {
+        users: vec![
+            ("alice".to_string(), "secret".to_string()),
+            ("bob".to_string(), "hunter2".to_string()),
+        ],
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+/// A state for `repo` running the real git.
+pub fn new_state(repo: &TempDir) -> State {
+    State::new(
+        repo.path().to_str().unwrap().to_string(),
+        Arc::new(ProcessRunner::default()),
+    )
+}
+
+/// A lock on `path`, its id differing from every other one made here.
+pub fn lock(path: &str, owner: &str, locked_at: &str) -> LockEntry {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+    LockEntry {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed).to_string(),
+        path: path.to_string(),
+        owner: Owner {
+            name: owner.to_string(),
+        },
+        locked_at: locked_at.to_string(),
+    }
+}
+
+/// How `git lfs` and the lock api show a lock on `path`.
+pub fn lock_json(id: u32, path: &str, owner: &str) -> String {
+    format!(
+        r#"{{"id":"{}","path":"{}","owner":{{"name":"{}"}},"locked_at":"2020-11-02T10:00:00Z"}}"#,
+        id, path, owner
+    )
+}
+
+/// Keeps the events or notifications it is given, oldest first.
+pub struct Recorder<T>(Mutex<Vec<T>>);
+
+impl<T> Default for Recorder<T> {
+    fn default() -> Self {
+        Self(Mutex::new(Vec::new()))
+    }
+}
+
+impl<T: Clone> Recorder<T> {
+    pub fn events(&self) -> Vec<T> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// The events so far, forgetting them.
+    pub fn take(&self) -> Vec<T> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl EventSink for Recorder<Event> {
+    fn emit(&self, event: &Event) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
+
+impl Notifier for Recorder<WatchEvent> {
+    fn notify(&self, event: &WatchEvent) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
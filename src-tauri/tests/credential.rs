@@ -0,0 +1,259 @@
+//! Direct lock api calls against `mock-lfs-server`, with a stand-in credential helper
+//! in place of a real one. Needs `git` on `PATH`, but not `git-lfs`.
+
+mod common;
+
+use backend_api::{Request, Response};
+use common::{git, start_server};
+use git_lock_manager::credential::{Credential, MemoryStore};
+use git_lock_manager::handler::{self, State};
+use git_lock_manager::lfs_api::{Endpoint, LfsClient, SshRemote};
+use git_lock_manager::network::NetworkSettings;
+use git_lock_manager::runner::{CommandOutput, GitCommand, ProcessRunner, RecordingRunner};
+use mock_lfs_server::{Config, MockServer};
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// A repo whose credential helper answers `alice` with `password` and logs each
+/// action it is asked for to `helper.log`.
+fn setup_repo(server: &MockServer, password: &str) -> TempDir {
+    let dir = common::server_repo(server);
+    let log = dir.path().join("helper.log");
+    let helper = format!(
+        "!f() {{ echo \"$1\" >> '{}'; test \"$1\" = get && echo username=alice && echo password={}; true; }}; f",
+        log.display(),
+        password
+    );
+    git(dir.path(), &["config", "credential.helper", &helper]);
+    dir
+}
+
+fn helper_log(repo: &TempDir) -> Vec<String> {
+    std::fs::read_to_string(repo.path().join("helper.log"))
+        .unwrap_or_default()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+fn new_state(repo: &TempDir) -> State {
+    let mut state = common::new_state(repo);
+    state.lfs_api = Some(Arc::new(LfsClient::new(
+        Arc::new(MemoryStore::default()),
+        NetworkSettings::default(),
+    )));
+    state
+}
+
+#[test]
+fn helper_credentials_are_used_and_approved() {
+    let server = start_server();
+    let repo = setup_repo(&server, "secret");
+    let state = new_state(&repo);
+
+    let response = handler::handle(
+        &state,
+        Request::LockFile {
+            path: "Content/Hero.uasset".to_string(),
+        },
+    )
+    .unwrap();
+
+    match response {
+        Response::LockFile { lock_entry } => assert_eq!(lock_entry.owner.name, "alice"),
+        r => panic!("unexpected response {:?}", r),
+    }
+    assert_eq!(server.locks().len(), 1);
+    assert_eq!(helper_log(&repo), vec!["get", "store"]);
+}
+
+#[test]
+fn refused_credentials_are_rejected() {
+    let server = start_server();
+    let repo = setup_repo(&server, "wrong");
+    let state = new_state(&repo);
+
+    let error = handler::handle(
+        &state,
+        Request::LockFile {
+            path: "Content/Hero.uasset".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    assert!(error.to_string().contains("401"), "{}", error);
+    assert!(server.locks().is_empty());
+    assert_eq!(helper_log(&repo), vec!["get", "erase"]);
+}
+
+#[test]
+fn credentials_round_trip() {
+    let credential =
+        Credential::for_url("https://bob@git.example.com:8443/team/game.git/info/lfs").unwrap();
+    assert_eq!(credential.protocol, "https");
+    assert_eq!(credential.host, "git.example.com:8443");
+    assert_eq!(credential.username.as_deref(), Some("bob"));
+
+    let filled = Credential::parse(
+        "protocol=https\nhost=git.example.com:8443\nusername=bob\npassword=a=b\n",
+    );
+    assert_eq!(filled.password.as_deref(), Some("a=b"));
+    assert_eq!(
+        filled.to_input(),
+        "protocol=https\nhost=git.example.com:8443\nusername=bob\npassword=a=b\n\n"
+    );
+}
+
+#[test]
+fn ssh_remotes_get_a_cached_token() {
+    assert_eq!(
+        Endpoint::from_remote("ssh://git@example.com:2222/team/game").unwrap(),
+        Endpoint {
+            url: "https://example.com/team/game.git/info/lfs".to_string(),
+            ssh: Some(SshRemote {
+                user_host: "git@example.com".to_string(),
+                port: Some("2222".to_string()),
+                path: "/team/game".to_string(),
+            }),
+        }
+    );
+
+    let server = start_server();
+    server.insert_lock("Content/Hero.uasset", "alice");
+    let href = server.url();
+    let runner = RecordingRunner::new(move |command| {
+        let stdout = match (
+            command.program.as_deref(),
+            command.args[..2].join(" ").as_str(),
+        ) {
+            (Some("ssh"), _) => format!(
+                r#"{{"href":"{}","header":{{"Authorization":"Basic YWxpY2U6c2VjcmV0"}},"expires_in":3600}}"#,
+                href
+            ),
+            (None, "config --get") if command.args[2] == "remote.origin.url" => {
+                "git@example.com:team/game.git\n".to_string()
+            }
+            _ => {
+                return Ok(CommandOutput {
+                    success: false,
+                    ..Default::default()
+                })
+            }
+        };
+        Ok(CommandOutput {
+            success: true,
+            stdout,
+            stderr: String::new(),
+        })
+    });
+    let client = LfsClient::new(Arc::new(MemoryStore::default()), NetworkSettings::default());
+    let repo = Path::new("/work/game");
+
+    for _ in 0..2 {
+        let locks = client.get_locked_files(&runner, repo).unwrap();
+        assert_eq!(locks.len(), 1);
+        assert_eq!(locks[0].path, "Content/Hero.uasset");
+    }
+    // a token revoked before it expires is replaced
+    server.fail_next(1, 401);
+    assert_eq!(client.get_locked_files(&runner, repo).unwrap().len(), 1);
+
+    let ssh: Vec<Vec<String>> = runner
+        .calls()
+        .into_iter()
+        .filter(|c| c.program.is_some())
+        .map(|c| c.args)
+        .collect();
+    let authenticate = vec![
+        "git@example.com",
+        "git-lfs-authenticate",
+        "team/game.git",
+        "download",
+    ];
+    assert_eq!(ssh, vec![authenticate.clone(), authenticate]);
+}
+
+#[test]
+fn every_page_of_locks_is_read() {
+    let server = MockServer::start(Config {
+        users: vec![("alice".to_string(), "secret".to_string())],
+        page_size: Some(2),
+        ..Default::default()
+    })
+    .unwrap();
+    let ids: Vec<String> = ["Hero", "Map", "Tree", "Crate", "Door"]
+        .iter()
+        .zip(&["alice", "bob", "bob", "alice", "alice"])
+        .map(|(name, owner)| {
+            server
+                .insert_lock(&format!("Content/{}.uasset", name), owner)
+                .id
+        })
+        .collect();
+    let repo = setup_repo(&server, "secret");
+    let runner = ProcessRunner::default();
+    let client = LfsClient::new(Arc::new(MemoryStore::default()), NetworkSettings::default());
+
+    let locks = client.get_locked_files(&runner, repo.path()).unwrap();
+    let own = client.get_own_lock_ids(&runner, repo.path()).unwrap();
+
+    assert_eq!(locks.len(), 5);
+    let mut own: Vec<String> = own.into_iter().collect();
+    own.sort();
+    assert_eq!(own, vec![ids[0].clone(), ids[3].clone(), ids[4].clone()]);
+}
+
+/// The command run for `git-lfs-authenticate`, with `core.sshCommand` set and git
+/// answering `exec_path` to `--exec-path`.
+fn ssh_call(exec_path: &Path) -> GitCommand {
+    let exec_path = format!("{}\n", exec_path.display());
+    let runner = RecordingRunner::new(move |command| {
+        let stdout = match command.args.get(2).map(String::as_str) {
+            _ if command.args == ["--exec-path"] => exec_path.as_str(),
+            Some("remote.origin.url") => "git@example.com:team/game.git\n",
+            Some("core.sshCommand") => "ssh -i \"/keys/my key\"\n",
+            _ => "",
+        };
+        Ok(CommandOutput {
+            success: command.program.is_none(),
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        })
+    });
+    let client = LfsClient::new(Arc::new(MemoryStore::default()), NetworkSettings::default());
+
+    assert!(client
+        .get_locked_files(&runner, Path::new("/work/game"))
+        .is_err());
+    runner.calls().pop().unwrap()
+}
+
+#[test]
+fn ssh_commands_run_through_the_shell() {
+    let ssh = ssh_call(Path::new(""));
+    assert_eq!(ssh.program.as_deref(), Some("sh"));
+    assert_eq!(
+        ssh.args,
+        vec![
+            "-c",
+            "ssh -i \"/keys/my key\" \"$@\"",
+            "ssh -i \"/keys/my key\"",
+            "git@example.com",
+            "git-lfs-authenticate",
+            "team/game.git",
+            "download",
+        ]
+    );
+
+    // git for windows, whose sh is usually not on `PATH`
+    let root = tempfile::tempdir().unwrap();
+    let exec_path = root.path().join("mingw64/libexec/git-core");
+    std::fs::create_dir_all(&exec_path).unwrap();
+    std::fs::create_dir_all(root.path().join("usr/bin")).unwrap();
+    let sh = root.path().join("usr/bin/sh.exe");
+    std::fs::write(&sh, "").unwrap();
+    let ssh = ssh_call(&exec_path);
+    assert_eq!(ssh.program, Some(sh.to_string_lossy().to_string()));
+    assert_eq!(ssh.args[0], "-c");
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use backend_api::{Event, Request};
+use common::{lock_json, Recorder};
+use git_lock_manager::handler::{self, State};
+use git_lock_manager::runner::{CommandOutput, RecordingRunner};
+use std::sync::{Arc, Mutex};
+
+/// A state whose `git lfs locks` answers with the locks in `server`.
+fn new_state(server: Arc<Mutex<Vec<String>>>) -> (State, Arc<Recorder<Event>>) {
+    let runner = RecordingRunner::new(move |command| {
+        let stdout = match command.args[1].as_str() {
+            "locks" => format!("[{}]", server.lock().unwrap().join(",")),
+            "lock" => lock_json(7, &command.args[2], "alice"),
+            _ => String::new(),
+        };
+        Ok(CommandOutput {
+            success: true,
+            stdout,
+            stderr: String::new(),
+        })
+    });
+    let state = State::new("/work/game".to_string(), Arc::new(runner));
+    let sink = Arc::new(Recorder::default());
+    state.events.subscribe(sink.clone());
+    (state, sink)
+}
+
+fn paths(events: &[Event]) -> Vec<String> {
+    events
+        .iter()
+        .map(|e| match e {
+            Event::LockAdded { lock } => format!("+{}", lock.path),
+            Event::LockRemoved { lock } => format!("-{}", lock.path),
+            e => panic!("unexpected event {:?}", e),
+        })
+        .collect()
+}
+
+#[test]
+fn refresh_emits_the_lock_changes() {
+    let server = Arc::new(Mutex::new(vec![
+        lock_json(1, "Content/Hero.uasset", "alice"),
+        lock_json(2, "Content/Map.umap", "alice"),
+    ]));
+    let (state, sink) = new_state(server.clone());
+
+    state.refresh_locks().unwrap();
+    assert_eq!(
+        paths(&sink.take()),
+        vec!["+Content/Hero.uasset", "+Content/Map.umap"]
+    );
+
+    *server.lock().unwrap() = vec![
+        lock_json(2, "Content/Map.umap", "alice"),
+        lock_json(3, "Content/Tree.uasset", "alice"),
+    ];
+    state.refresh_locks().unwrap();
+    assert_eq!(
+        paths(&sink.take()),
+        vec!["-Content/Hero.uasset", "+Content/Tree.uasset"]
+    );
+
+    state.refresh_locks().unwrap();
+    assert!(sink.take().is_empty());
+}
+
+#[test]
+fn own_lock_and_unlock_are_emitted() {
+    let (state, sink) = new_state(Arc::new(Mutex::new(Vec::new())));
+    let request = |r| handler::handle(&state, r).unwrap();
+
+    request(Request::LockFile {
+        path: "Content/Hero.uasset".to_string(),
+    });
+    request(Request::UnlockFile { id: 7 });
+
+    assert_eq!(
+        paths(&sink.take()),
+        vec!["+Content/Hero.uasset", "-Content/Hero.uasset"]
+    );
+}
@@ -0,0 +1,99 @@
+use backend_api::{ExportFormat, Request, Response};
+use chrono::{DateTime, Utc};
+use git_lock_manager::export::{self, LockReport};
+use git_lock_manager::handler::{self, State};
+use git_lock_manager::runner::{CommandOutput, RecordingRunner};
+use std::sync::Arc;
+
+fn report(path: &str, owner: &str) -> LockReport {
+    LockReport {
+        path: path.to_string(),
+        owner: owner.to_string(),
+        locked_at: "2020-11-02T10:00:00Z".to_string(),
+        age: "1d 2h".to_string(),
+        status: "clean".to_string(),
+    }
+}
+
+#[test]
+fn fields_are_escaped_per_format() {
+    let reports = vec![report("Content/Hero, \"final\".uasset", "<bob> | co")];
+
+    let csv = export::render(ExportFormat::Csv, "Locks", &reports).unwrap();
+    assert_eq!(
+        csv.lines().nth(1).unwrap(),
+        "\"Content/Hero, \"\"final\"\".uasset\",<bob> | co,2020-11-02T10:00:00Z,1d 2h,clean"
+    );
+
+    let markdown = export::render(ExportFormat::Markdown, "Locks", &reports).unwrap();
+    assert!(markdown.starts_with("# Locks\n"));
+    assert!(markdown.contains("| Content/Hero, \"final\".uasset | <bob> \\| co |"));
+
+    let html = export::render(ExportFormat::Html, "Locks & more", &reports).unwrap();
+    assert!(html.contains("<title>Locks &amp; more</title>"));
+    assert!(
+        html.contains("<td>Content/Hero, &quot;final&quot;.uasset</td><td>&lt;bob&gt; | co</td>")
+    );
+
+    let json = export::render(ExportFormat::Json, "Locks", &reports).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed[0]["owner"], "<bob> | co");
+}
+
+#[test]
+fn age_keeps_the_two_largest_units() {
+    let now: DateTime<Utc> = "2020-11-03T12:30:00Z".parse().unwrap();
+    assert_eq!(export::age("2020-11-03T12:05:00Z", now), "25m");
+    assert_eq!(export::age("2020-11-03T09:00:00+00:00", now), "3h 30m");
+    assert_eq!(export::age("2020-11-01T10:00:00Z", now), "2d 2h");
+    assert_eq!(export::age("yesterday", now), "");
+}
+
+#[test]
+fn export_honors_the_filter() {
+    let runner = RecordingRunner::new(|command| {
+        let stdout = match command.args[0].as_str() {
+            "status" => " M Content/Hero.uasset\0",
+            _ => {
+                r#"[{"id":"1","path":"Content/Hero.uasset","owner":{"name":"alice"},"locked_at":"2020-11-02T10:00:00Z"},
+                    {"id":"2","path":"Content/Map.umap","owner":{"name":"bob"},"locked_at":"2020-11-02T10:00:00Z"},
+                    {"id":"3","path":"Content/Tree.uasset","owner":{"name":"bob"},"locked_at":"2020-11-02T10:00:00Z"}]"#
+            }
+        };
+        Ok(CommandOutput {
+            success: true,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        })
+    });
+    let state = State::new("/work/game".to_string(), Arc::new(runner));
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("locks.csv");
+
+    let response = handler::handle(
+        &state,
+        Request::ExportLocks {
+            filter: "ext:uasset".to_string(),
+            format: ExportFormat::Csv,
+            path: Some(path.to_string_lossy().to_string()),
+        },
+    )
+    .unwrap();
+
+    match response {
+        Response::ExportLocks { count, .. } => assert_eq!(count, 2),
+        r => panic!("unexpected response {:?}", r),
+    }
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let rows: Vec<&str> = csv.lines().skip(1).collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows[0].starts_with("Content/Hero.uasset,alice,"));
+    assert!(rows[0].ends_with(",modified"));
+    assert!(rows[1].starts_with("Content/Tree.uasset,bob,"));
+    assert!(rows[1].ends_with(",clean"));
+    // what the http api answers with instead of writing a file
+    let (contents, count) = state.export_locks("ext:uasset", ExportFormat::Csv).unwrap();
+    assert_eq!(count, 2);
+    assert!(contents.starts_with(csv.lines().next().unwrap()));
+    assert!(contents.contains("\nContent/Tree.uasset,bob,"));
+}
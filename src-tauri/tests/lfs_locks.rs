@@ -0,0 +1,311 @@
+//! End to end lock flows against `mock-lfs-server`. Needs `git` and `git-lfs` on `PATH`.
+
+mod common;
+
+use backend_api::{LockEntry, Request, Response, WatchEvent};
+use common::{git, new_state, start_server, Recorder};
+use git_lock_manager::handler::{self, State};
+use mock_lfs_server::MockServer;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+const ASSET: &str = "Content/Hero.uasset";
+
+/// Creates a repository with one committed lfs asset, talking to `server` as `alice`.
+fn setup_repo(server: &MockServer) -> TempDir {
+    let dir = common::server_repo(server);
+    let repo = dir.path();
+    git(
+        repo,
+        &[
+            "config",
+            "credential.helper",
+            "!f() { echo username=alice; echo password=secret; }; f",
+        ],
+    );
+    git(repo, &["config", "user.name", "alice"]);
+    git(repo, &["config", "user.email", "alice@example.com"]);
+    git(repo, &["lfs", "install", "--local"]);
+    std::fs::write(
+        repo.join(".gitattributes"),
+        "*.uasset filter=lfs diff=lfs merge=lfs -text lockable\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(repo.join("Content")).unwrap();
+    std::fs::write(repo.join(ASSET), "binary").unwrap();
+    git(repo, &["add", "."]);
+    git(repo, &["commit", "-q", "-m", "init"]);
+    dir
+}
+
+fn lock(state: &State, path: &str) -> anyhow::Result<Response> {
+    handler::handle(
+        state,
+        Request::LockFile {
+            path: path.to_string(),
+        },
+    )
+}
+
+fn locked_files(state: &State) -> Vec<LockEntry> {
+    let response = handler::handle(
+        state,
+        Request::GetLockedFiles {
+            offset: 0,
+            limit: None,
+            cached: false,
+        },
+    );
+    match response.unwrap() {
+        Response::GetLockedFiles {
+            locked_files,
+            total,
+            offline: None,
+        } => {
+            assert_eq!(locked_files.len(), total);
+            locked_files
+        }
+        r => panic!("unexpected response {:?}", r),
+    }
+}
+
+#[test]
+fn lock_list_unlock() {
+    let server = start_server();
+    let repo = setup_repo(&server);
+    let state = new_state(&repo);
+
+    let lock_entry = match lock(&state, ASSET).unwrap() {
+        Response::LockFile { lock_entry } => lock_entry,
+        r => panic!("unexpected response {:?}", r),
+    };
+    assert_eq!(lock_entry.path, ASSET);
+    assert_eq!(lock_entry.owner.name, "alice");
+    assert_eq!(server.locks().len(), 1);
+
+    let listed = locked_files(&state);
+    assert_eq!(listed, vec![lock_entry.clone()]);
+
+    let id: u32 = lock_entry.id.parse().unwrap();
+    handler::handle(&state, Request::UnlockFile { id }).unwrap();
+    assert!(server.locks().is_empty());
+    assert!(locked_files(&state).is_empty());
+}
+
+#[test]
+fn lock_held_by_someone_else_fails() {
+    let server = start_server();
+    let repo = setup_repo(&server);
+    let state = new_state(&repo);
+    server.insert_lock(ASSET, "bob");
+
+    assert!(lock(&state, ASSET).is_err());
+    let locks = server.locks();
+    assert_eq!(locks.len(), 1);
+    assert_eq!(locks[0].owner.name, "bob");
+    assert_eq!(locked_files(&state)[0].owner.name, "bob");
+}
+
+#[test]
+fn server_failure_is_reported_and_recovers() {
+    let server = start_server();
+    let repo = setup_repo(&server);
+    let state = new_state(&repo);
+
+    server.fail_next(1, 500);
+    assert!(lock(&state, ASSET).is_err());
+    assert!(server.locks().is_empty());
+
+    assert!(lock(&state, ASSET).is_ok());
+    assert_eq!(server.locks().len(), 1);
+}
+
+#[test]
+fn search_lists_lfs_files() {
+    let server = start_server();
+    let repo = setup_repo(&server);
+    let state = new_state(&repo);
+
+    let response = handler::handle(
+        &state,
+        Request::GetFilteredFiles {
+            filter: "hero".to_string(),
+            offset: 0,
+            limit: Some(50),
+            all_files: false,
+        },
+    );
+    match response.unwrap() {
+        Response::GetFilteredFiles {
+            filtered_files,
+            total,
+        } => {
+            assert_eq!(total, 1);
+            assert_eq!(filtered_files.len(), 1);
+            assert_eq!(filtered_files[0].path, ASSET);
+            assert!(filtered_files[0].lfs);
+            assert!(filtered_files[0].lockable);
+        }
+        r => panic!("unexpected response {:?}", r),
+    }
+}
+
+#[test]
+fn search_all_tracked_files() {
+    let server = start_server();
+    let repo = setup_repo(&server);
+    let state = new_state(&repo);
+
+    let response = handler::handle(
+        &state,
+        Request::GetFilteredFiles {
+            filter: "gitattributes".to_string(),
+            offset: 0,
+            limit: None,
+            all_files: true,
+        },
+    );
+    match response.unwrap() {
+        Response::GetFilteredFiles { filtered_files, .. } => {
+            assert_eq!(filtered_files[0].path, ".gitattributes");
+            assert!(!filtered_files[0].lfs);
+            assert!(!filtered_files[0].lockable);
+        }
+        r => panic!("unexpected response {:?}", r),
+    }
+}
+
+#[test]
+fn pinned_and_recent_files() {
+    let server = start_server();
+    let repo = setup_repo(&server);
+    let state = new_state(&repo);
+
+    lock(&state, ASSET).unwrap();
+    handler::handle(
+        &state,
+        Request::PinFile {
+            path: ".gitattributes".to_string(),
+            pinned: true,
+        },
+    )
+    .unwrap();
+
+    let response = handler::handle(&state, Request::GetQuickAccess);
+    match response.unwrap() {
+        Response::GetQuickAccess { pinned, recent, .. } => {
+            assert_eq!(pinned.len(), 1);
+            assert_eq!(pinned[0].path, ".gitattributes");
+            assert_eq!(recent.len(), 1);
+            assert_eq!(recent[0].path, ASSET);
+            assert!(recent[0].lfs);
+        }
+        r => panic!("unexpected response {:?}", r),
+    }
+}
+
+#[test]
+fn unlock_mine_skips_other_users() {
+    let server = start_server();
+    let repo = setup_repo(&server);
+    let state = new_state(&repo);
+    let theirs = server.insert_lock("Content/Villain.uasset", "bob");
+    lock(&state, ASSET).unwrap();
+    std::fs::write(repo.path().join(ASSET), "changed").unwrap();
+
+    let preview = match handler::handle(&state, Request::PreviewUnlockMine).unwrap() {
+        Response::PreviewUnlockMine { locks } => locks,
+        r => panic!("unexpected response {:?}", r),
+    };
+    assert_eq!(preview.len(), 1);
+    assert_eq!(preview[0].lock.path, ASSET);
+    assert!(preview[0].modified);
+
+    let ids = vec![
+        preview[0].lock.id.parse().unwrap(),
+        theirs.id.parse().unwrap(),
+        999,
+    ];
+    let result = match handler::handle(&state, Request::UnlockMine { ids }).unwrap() {
+        Response::UnlockMine { result } => result,
+        r => panic!("unexpected response {:?}", r),
+    };
+    assert_eq!(result.done, vec![ASSET.to_string()]);
+    assert_eq!(result.failed.len(), 2);
+    assert_eq!(result.failed[0].path, "Content/Villain.uasset");
+    assert_eq!(result.failed[1].path, "999");
+    assert_eq!(server.locks().len(), 1);
+}
+
+#[test]
+fn watched_file_changes_are_notified() {
+    let server = start_server();
+    let repo = setup_repo(&server);
+    let mut state = new_state(&repo);
+    let notifier = Arc::new(Recorder::<WatchEvent>::default());
+    state.notifier = Some(notifier.clone());
+    handler::handle(
+        &state,
+        Request::WatchFile {
+            path: ASSET.to_string(),
+            watched: true,
+        },
+    )
+    .unwrap();
+
+    state.refresh_locks().unwrap();
+    server.insert_lock(ASSET, "bob");
+    server.insert_lock("Content/Other.uasset", "bob");
+    state.refresh_locks().unwrap();
+
+    let events = notifier.events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].describe(), format!("bob locked {}", ASSET));
+    match handler::handle(&state, Request::GetInbox).unwrap() {
+        Response::GetInbox { events: inbox } => assert_eq!(inbox, events),
+        r => panic!("unexpected response {:?}", r),
+    }
+}
+
+#[test]
+fn queued_lock_is_acquired_once_released() {
+    let server = start_server();
+    let repo = setup_repo(&server);
+    let mut state = new_state(&repo);
+    let notifier = Arc::new(Recorder::<WatchEvent>::default());
+    state.notifier = Some(notifier.clone());
+    server.insert_lock(ASSET, "bob");
+    let queue = |queued: bool| {
+        handler::handle(
+            &state,
+            Request::QueueLock {
+                path: ASSET.to_string(),
+                queued,
+            },
+        )
+        .unwrap()
+    };
+
+    queue(true);
+    state.refresh_locks().unwrap();
+    assert_eq!(server.locks()[0].owner.name, "bob");
+    assert!(notifier.events().is_empty());
+
+    server.remove_lock(ASSET).unwrap();
+    state.refresh_locks().unwrap();
+    let locks = server.locks();
+    assert_eq!(locks.len(), 1);
+    assert_eq!(locks[0].owner.name, "alice");
+    let events = notifier.events();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].automatic);
+
+    // nothing is queued anymore, so a later release is left alone
+    server.remove_lock(ASSET).unwrap();
+    server.insert_lock(ASSET, "bob");
+    queue(true);
+    queue(false);
+    server.remove_lock(ASSET).unwrap();
+    state.refresh_locks().unwrap();
+    assert!(server.locks().is_empty());
+}
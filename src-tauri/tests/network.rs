@@ -0,0 +1,175 @@
+//! Proxy and tls settings reaching git and the direct lock api, with a `CONNECT`
+//! proxy stand-in in front of `mock-lfs-server`. Needs `git` on `PATH`.
+
+mod common;
+
+use common::{git, new_repo, server_repo};
+use git_lock_manager::credential::MemoryStore;
+use git_lock_manager::lfs_api::LfsClient;
+use git_lock_manager::network::{self, NetworkSettings};
+use git_lock_manager::runner::{CommandRunner, GitCommand, ProcessRunner, RunnerSettings};
+use mock_lfs_server::{Config, MockServer};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Tunnels `CONNECT` requests to their target, keeping each request head.
+fn start_proxy() -> (u16, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let heads = Arc::new(Mutex::new(Vec::new()));
+    let seen = heads.clone();
+    std::thread::spawn(move || {
+        for client in listener.incoming().flatten() {
+            let heads = heads.clone();
+            std::thread::spawn(move || tunnel(client, &heads));
+        }
+    });
+    (port, seen)
+}
+
+fn tunnel(mut client: TcpStream, heads: &Mutex<Vec<String>>) {
+    let mut reader = BufReader::new(client.try_clone().unwrap());
+    let mut head = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap() == 0 {
+            return;
+        }
+        if line == "\r\n" {
+            break;
+        }
+        head.push_str(&line);
+    }
+    let target = head.split_whitespace().nth(1).unwrap().to_string();
+    heads.lock().unwrap().push(head);
+    let mut server = TcpStream::connect(target).unwrap();
+    client
+        .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+        .unwrap();
+    let (mut from_server, mut to_client) = (server.try_clone().unwrap(), client);
+    std::thread::spawn(move || std::io::copy(&mut from_server, &mut to_client));
+    let _ = std::io::copy(&mut reader, &mut server);
+}
+
+#[test]
+fn settings_reach_git_config() {
+    let (repo, other) = (new_repo(), new_repo());
+    let repo_path = repo.path().to_str().unwrap().to_string();
+    let settings: NetworkSettings = serde_json::from_value(serde_json::json!({
+        "proxy": "http://proxy.office:3128",
+        "repos": { repo_path.as_str(): { "ca_info": "/etc/office-ca.pem" } }
+    }))
+    .unwrap();
+    let mut git_settings = RunnerSettings::default();
+    settings.apply(&mut git_settings);
+    let runner = ProcessRunner::new(git_settings);
+    let config = |dir: &Path, key: &str| {
+        let output = runner
+            .run(&GitCommand::new(dir).args(["config", "--get", key]))
+            .unwrap();
+        output.stdout.trim().to_string()
+    };
+
+    assert_eq!(
+        config(repo.path(), "http.proxy"),
+        "http://proxy.office:3128"
+    );
+    assert_eq!(config(repo.path(), "http.sslCAInfo"), "/etc/office-ca.pem");
+    assert_eq!(
+        config(other.path(), "http.proxy"),
+        "http://proxy.office:3128"
+    );
+    assert_eq!(config(other.path(), "http.sslCAInfo"), "");
+}
+
+#[test]
+fn config_already_in_the_environment_is_kept() {
+    let repo = new_repo();
+    let settings: NetworkSettings = serde_json::from_value(serde_json::json!({
+        "proxy": "http://proxy.office:3128"
+    }))
+    .unwrap();
+    let mut git_settings = RunnerSettings::default();
+    for (key, value) in &[
+        ("GIT_CONFIG_COUNT", "1"),
+        ("GIT_CONFIG_KEY_0", "user.name"),
+        ("GIT_CONFIG_VALUE_0", "Alice"),
+    ] {
+        git_settings.env.insert(key.to_string(), value.to_string());
+    }
+    settings.apply(&mut git_settings);
+    let runner = ProcessRunner::new(git_settings);
+    let config = |key: &str| {
+        let output = runner
+            .run(&GitCommand::new(repo.path()).args(["config", "--get", key]))
+            .unwrap();
+        output.stdout.trim().to_string()
+    };
+
+    assert_eq!(config("user.name"), "Alice");
+    assert_eq!(config("http.proxy"), "http://proxy.office:3128");
+}
+
+#[test]
+fn lock_calls_go_through_the_proxy() {
+    let server = MockServer::start(Config::default()).unwrap();
+    server.insert_lock("Content/Hero.uasset", "alice");
+    let (port, heads) = start_proxy();
+    let repo = server_repo(&server);
+    let settings: NetworkSettings = serde_json::from_value(serde_json::json!({
+        "proxy": format!("http://carol:pw@127.0.0.1:{}", port)
+    }))
+    .unwrap();
+    let client = LfsClient::new(Arc::new(MemoryStore::default()), settings);
+
+    let locks = client
+        .get_locked_files(&ProcessRunner::default(), repo.path())
+        .unwrap();
+
+    assert_eq!(locks.len(), 1);
+    let heads = heads.lock().unwrap();
+    assert_eq!(heads.len(), 1);
+    let target = server.url().trim_start_matches("http://").to_string();
+    assert!(heads[0].starts_with(&format!("CONNECT {} HTTP/1.1", target)));
+    assert!(heads[0].contains("Proxy-Authorization: basic Y2Fyb2w6cHc="));
+}
+
+#[test]
+fn a_missing_ca_bundle_is_reported() {
+    let server = MockServer::start(Config::default()).unwrap();
+    let repo = server_repo(&server);
+    let settings: NetworkSettings =
+        serde_json::from_value(serde_json::json!({ "ca_info": "/nonexistent/ca.pem" })).unwrap();
+    let client = LfsClient::new(Arc::new(MemoryStore::default()), settings);
+
+    let error = client
+        .get_locked_files(&ProcessRunner::default(), repo.path())
+        .unwrap_err();
+
+    assert!(
+        error.to_string().contains("/nonexistent/ca.pem"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn a_closed_port_is_unreachable() {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let repo = new_repo();
+    let url = format!("http://127.0.0.1:{}/game.git/info/lfs", port);
+    git(repo.path(), &["config", "lfs.url", &url]);
+    let client = LfsClient::new(Arc::new(MemoryStore::default()), NetworkSettings::default());
+
+    let error = client
+        .get_locked_files(&ProcessRunner::default(), repo.path())
+        .unwrap_err();
+
+    assert!(network::is_unreachable(&error), "{}", error);
+}
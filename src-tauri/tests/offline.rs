@@ -0,0 +1,339 @@
+mod common;
+
+use backend_api::{BatchFailure, BatchResult, Event, Offline, PendingOperation, Request, Response};
+use common::{lock_json, Recorder};
+use git_lock_manager::handler::{self, State};
+use git_lock_manager::offline_queue::OfflineQueue;
+use git_lock_manager::runner::{CommandOutput, RecordingRunner};
+use git_lock_manager::snapshot::Snapshots;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const REPO: &str = "/work/game";
+
+/// Locks as `(id, path, owner)`, we are bob.
+struct Server {
+    reachable: bool,
+    locks: Vec<(u32, String, String)>,
+}
+
+impl Server {
+    fn new(locks: &[(u32, &str, &str)]) -> Arc<Mutex<Server>> {
+        Arc::new(Mutex::new(Server {
+            reachable: true,
+            locks: locks
+                .iter()
+                .map(|(id, path, owner)| (*id, path.to_string(), owner.to_string()))
+                .collect(),
+        }))
+    }
+
+    fn paths(&self) -> Vec<String> {
+        self.locks.iter().map(|(_, path, _)| path.clone()).collect()
+    }
+}
+
+fn list_json<'a>(locks: impl Iterator<Item = &'a (u32, String, String)>) -> String {
+    let locks: Vec<String> = locks
+        .map(|(id, path, owner)| lock_json(*id, path, owner))
+        .collect();
+    format!("[{}]", locks.join(","))
+}
+
+/// Answers `git lfs` like `server` would. Git lfs knows our lock on
+/// `Content/Mine.uasset` locally.
+fn reply(server: &Mutex<Server>, args: &[String]) -> Result<String, String> {
+    let mut server = server.lock().unwrap();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if args == ["lfs", "locks", "--local", "--json"] {
+        return Ok(format!("[{}]", lock_json(9, "Content/Mine.uasset", "bob")));
+    }
+    if !server.reachable {
+        return Err("dial tcp: connection refused".to_string());
+    }
+    match args[..] {
+        ["lfs", "locks", "--json"] => Ok(list_json(server.locks.iter())),
+        ["lfs", "locks", "--verify", "--json"] => Ok(format!(
+            r#"{{"ours":{},"theirs":[]}}"#,
+            list_json(server.locks.iter().filter(|l| l.2 == "bob"))
+        )),
+        ["lfs", "lock", path, "--json"] => {
+            if server.locks.iter().any(|l| l.1 == path) {
+                return Err("lock exists".to_string());
+            }
+            let id = server.locks.iter().map(|l| l.0).max().unwrap_or(0) + 1;
+            server.locks.push((id, path.to_string(), "bob".to_string()));
+            Ok(lock_json(id, path, "bob"))
+        }
+        ["lfs", "unlock", "-i", id] => {
+            server.locks.retain(|l| l.0.to_string() != id);
+            Ok(String::new())
+        }
+        _ => Ok(String::new()),
+    }
+}
+
+/// A state talking to `server` that keeps its snapshots and offline queue in `dir`.
+fn new_state(server: Arc<Mutex<Server>>, dir: &Path) -> State {
+    let runner = RecordingRunner::new(move |command| {
+        let (success, stdout, stderr) = match reply(&server, &command.args) {
+            Ok(stdout) => (true, stdout, String::new()),
+            Err(stderr) => (false, String::new(), stderr),
+        };
+        Ok(CommandOutput {
+            success,
+            stdout,
+            stderr,
+        })
+    });
+    let mut state = State::new(REPO.to_string(), Arc::new(runner));
+    state.snapshots = Arc::new(Mutex::new(Snapshots::load(dir.join("lock-snapshots.json"))));
+    state.offline_queue = Arc::new(Mutex::new(OfflineQueue::load(
+        dir.join("offline-queue.json"),
+    )));
+    state
+}
+
+fn subscribe(state: &State) -> Arc<Recorder<Event>> {
+    let sink = Arc::new(Recorder::default());
+    state.events.subscribe(sink.clone());
+    sink
+}
+
+fn locked_files(state: &State) -> (Vec<String>, Option<Offline>) {
+    let response = handler::handle(
+        state,
+        Request::GetLockedFiles {
+            offset: 0,
+            limit: None,
+            cached: false,
+        },
+    );
+    match response.unwrap() {
+        Response::GetLockedFiles {
+            locked_files,
+            offline,
+            ..
+        } => (locked_files.into_iter().map(|l| l.path).collect(), offline),
+        r => panic!("unexpected response {:?}", r),
+    }
+}
+
+fn pending(request: Request, state: &State) -> PendingOperation {
+    match handler::handle(state, request).unwrap() {
+        Response::Pending { operation } => operation,
+        r => panic!("unexpected response {:?}", r),
+    }
+}
+
+#[test]
+fn the_last_snapshot_is_listed_while_offline() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::new(&[(1, "Content/Hero.uasset", "alice")]);
+    let (paths, offline) = locked_files(&new_state(server.clone(), dir.path()));
+    assert_eq!(paths, vec!["Content/Hero.uasset"]);
+    assert_eq!(offline, None);
+
+    // the app restarts without a connection
+    server.lock().unwrap().reachable = false;
+    let state = new_state(server.clone(), dir.path());
+    let sink = subscribe(&state);
+
+    let (paths, offline) = locked_files(&state);
+    assert_eq!(paths, vec!["Content/Hero.uasset", "Content/Mine.uasset"]);
+    let offline = offline.unwrap();
+    assert!(offline.since.is_some());
+    assert!(
+        offline.error.contains("connection refused"),
+        "{}",
+        offline.error
+    );
+    assert!(sink.events().contains(&Event::ConnectionChanged {
+        offline: Some(offline)
+    }));
+
+    {
+        let mut server = server.lock().unwrap();
+        server.reachable = true;
+        server.locks.clear();
+    }
+    let (paths, offline) = locked_files(&state);
+    assert!(paths.is_empty());
+    assert_eq!(offline, None);
+    assert_eq!(
+        sink.events().last(),
+        Some(&Event::ConnectionChanged { offline: None })
+    );
+}
+
+#[test]
+fn without_a_snapshot_only_local_locks_are_listed() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::new(&[]);
+    server.lock().unwrap().reachable = false;
+    let state = new_state(server, dir.path());
+
+    let (paths, offline) = locked_files(&state);
+
+    assert_eq!(paths, vec!["Content/Mine.uasset"]);
+    assert_eq!(offline.unwrap().since, None);
+}
+
+#[test]
+fn operations_while_offline_are_replayed_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::new(&[
+        (1, "Content/Hero.uasset", "alice"),
+        (2, "Content/Map.umap", "bob"),
+    ]);
+    let state = new_state(server.clone(), dir.path());
+    locked_files(&state);
+
+    server.lock().unwrap().reachable = false;
+    let lock = |path: &str| Request::LockFile {
+        path: path.to_string(),
+    };
+    assert!(pending(lock("Content/Tree.uasset"), &state).lock);
+    assert!(pending(lock("Content/Hero.uasset"), &state).lock);
+    let unlock = pending(Request::UnlockFile { id: 2 }, &state);
+    assert_eq!(unlock.path, "Content/Map.umap");
+    assert_eq!(unlock.id.as_deref(), Some("2"));
+    assert_eq!(server.lock().unwrap().locks.len(), 2);
+
+    // the queue outlives a restart
+    let state = new_state(server.clone(), dir.path());
+    assert_eq!(state.offline_queue.lock().unwrap().pending(REPO).len(), 3);
+    server.lock().unwrap().reachable = true;
+    let sink = subscribe(&state);
+
+    let (paths, offline) = locked_files(&state);
+
+    assert_eq!(offline, None);
+    assert_eq!(paths, vec!["Content/Hero.uasset", "Content/Tree.uasset"]);
+    assert_eq!(server.lock().unwrap().paths(), paths);
+    assert!(sink.events().contains(&Event::PendingReplayed {
+        result: BatchResult {
+            done: vec![
+                "Content/Tree.uasset".to_string(),
+                "Content/Map.umap".to_string()
+            ],
+            failed: vec![BatchFailure {
+                path: "Content/Hero.uasset".to_string(),
+                error: "already locked by alice".to_string(),
+            }],
+        }
+    }));
+    assert!(state.offline_queue.lock().unwrap().pending(REPO).is_empty());
+}
+
+#[test]
+fn a_pending_lock_and_unlock_cancel_out() {
+    let operation = |path: &str, lock: bool| PendingOperation {
+        path: path.to_string(),
+        lock,
+        id: None,
+        time: "2020-11-02T10:00:00Z".to_string(),
+    };
+    let mut queue = OfflineQueue::default();
+    queue
+        .push(REPO, operation("Content/Hero.uasset", true))
+        .unwrap();
+    queue
+        .push(REPO, operation("Content/Map.umap", false))
+        .unwrap();
+    queue
+        .push(REPO, operation("Content/Hero.uasset", false))
+        .unwrap();
+
+    assert_eq!(
+        queue.pending(REPO),
+        vec![operation("Content/Map.umap", false)]
+    );
+}
+
+#[test]
+fn removing_a_replayed_operation_keeps_newer_ones() {
+    let operation = |lock: bool, time: &str| PendingOperation {
+        path: "Content/Hero.uasset".to_string(),
+        lock,
+        id: None,
+        time: time.to_string(),
+    };
+    let mut queue = OfflineQueue::default();
+    queue
+        .push(REPO, operation(true, "2020-11-02T10:00:00Z"))
+        .unwrap();
+    let replaying = queue.pending(REPO).remove(0);
+    // asked for again while the first lock is being replayed
+    queue
+        .push(REPO, operation(false, "2020-11-02T10:01:00Z"))
+        .unwrap();
+    queue
+        .push(REPO, operation(true, "2020-11-02T10:02:00Z"))
+        .unwrap();
+
+    queue.remove(REPO, &replaying).unwrap();
+
+    assert_eq!(
+        queue.pending(REPO),
+        vec![operation(true, "2020-11-02T10:02:00Z")]
+    );
+}
+
+#[test]
+fn picking_another_repo_forgets_being_offline() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::new(&[(1, "Content/Hero.uasset", "alice")]);
+    let state = new_state(server.clone(), dir.path());
+    server.lock().unwrap().reachable = false;
+    let (_, offline) = locked_files(&state);
+    assert!(offline.is_some());
+    let sink = subscribe(&state);
+
+    // the other repository's server answers
+    server.lock().unwrap().reachable = true;
+    state.open_repo(Path::new("/work/tools")).unwrap();
+
+    assert_eq!(*state.offline.lock().unwrap(), None);
+    assert!(sink
+        .events()
+        .contains(&Event::ConnectionChanged { offline: None }));
+    let (paths, offline) = locked_files(&state);
+    assert_eq!(paths, vec!["Content/Hero.uasset"]);
+    assert_eq!(offline, None);
+    let lock = Request::LockFile {
+        path: "Content/Tree.uasset".to_string(),
+    };
+    assert!(matches!(
+        handler::handle(&state, lock).unwrap(),
+        Response::LockFile { .. }
+    ));
+}
+
+#[test]
+fn operations_kept_for_a_repo_are_replayed_when_it_is_picked_again() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = Server::new(&[]);
+    let state = new_state(server.clone(), dir.path());
+    locked_files(&state);
+    server.lock().unwrap().reachable = false;
+    let lock = Request::LockFile {
+        path: "Content/Tree.uasset".to_string(),
+    };
+    pending(lock, &state);
+
+    server.lock().unwrap().reachable = true;
+    state.open_repo(Path::new("/work/tools")).unwrap();
+    assert!(server.lock().unwrap().locks.is_empty());
+    let sink = subscribe(&state);
+    state.open_repo(Path::new(REPO)).unwrap();
+
+    assert_eq!(server.lock().unwrap().paths(), vec!["Content/Tree.uasset"]);
+    assert!(sink.events().contains(&Event::PendingReplayed {
+        result: BatchResult {
+            done: vec!["Content/Tree.uasset".to_string()],
+            failed: Vec::new(),
+        }
+    }));
+    assert!(state.offline_queue.lock().unwrap().pending(REPO).is_empty());
+}
@@ -0,0 +1,60 @@
+use backend_api::{Envelope, Request, Response, PROTOCOL_VERSION};
+use git_lock_manager::handler::{self, State};
+use git_lock_manager::runner::{CommandOutput, RecordingRunner};
+use std::sync::Arc;
+
+fn new_state() -> State {
+    let runner = RecordingRunner::new(|_| {
+        Ok(CommandOutput {
+            success: true,
+            stdout: String::new(),
+            stderr: String::new(),
+        })
+    });
+    State::new(String::new(), Arc::new(runner))
+}
+
+#[test]
+fn response_answers_the_request_id() {
+    // tauri adds its promise callbacks next to the envelope
+    let call = r#"{"callback":"_1","error":"_2","version":1,"id":42,
+        "payload":{"cmd":"echo","message":"hi"}}"#;
+    let envelope: Envelope<Request> = serde_json::from_str(call).unwrap();
+
+    let response = handler::dispatch(&new_state(), envelope).unwrap();
+
+    assert_eq!(response.id, 42);
+    match response.payload {
+        Response::Echo { message } => assert_eq!(message, "hi"),
+        r => panic!("unexpected response {:?}", r),
+    }
+}
+
+#[test]
+fn hello_settles_on_a_common_version() {
+    let state = new_state();
+    let hello = |version| handler::handle(&state, Request::Hello { version });
+
+    match hello(PROTOCOL_VERSION + 5).unwrap() {
+        Response::Hello { version, repo } => {
+            assert_eq!(version, PROTOCOL_VERSION);
+            assert_eq!(repo, "");
+        }
+        r => panic!("unexpected response {:?}", r),
+    }
+    let error = hello(0).unwrap_err().to_string();
+    assert!(error.contains("protocol version 0"), "{}", error);
+}
+
+#[test]
+fn unsupported_envelope_version_is_rejected() {
+    let envelope = Envelope {
+        version: PROTOCOL_VERSION + 1,
+        id: 1,
+        payload: Request::GetInbox,
+    };
+
+    let error = handler::dispatch(&new_state(), envelope).unwrap_err();
+
+    assert!(error.to_string().contains("not supported"), "{}", error);
+}
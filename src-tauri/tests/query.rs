@@ -0,0 +1,90 @@
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use common::lock;
+use git_lock_manager::query::{Age, Context, Filter, Locked, Query};
+use std::collections::HashSet;
+
+#[test]
+fn filters_and_free_text() {
+    let query =
+        Query::parse(r#"hero owner:Alice,bob in:"Content/My Folder" age:>7d locked:mine"#).unwrap();
+    assert_eq!(query.text, "hero");
+    assert_eq!(
+        query.filters,
+        vec![
+            Filter::Owner(vec!["alice".to_string(), "bob".to_string()]),
+            Filter::In(vec!["content/my folder".to_string()]),
+            Filter::Age(Age::OlderThan(Duration::days(7))),
+            Filter::Locked(Locked::Mine),
+        ]
+    );
+    assert!(query.needs_own_locks());
+    assert!(!query.needs_status());
+}
+
+#[test]
+fn parse_errors() {
+    for input in &[
+        "colour:red",
+        "locked:maybe",
+        "age:soon",
+        "age:>99999999999999d",
+        "age:<99999999999999999999w",
+        "owner:",
+        r#"in:"Content"#,
+    ] {
+        let error = Query::parse(input).unwrap_err().to_string();
+        assert!(error.starts_with("invalid query"), "{}: {}", input, error);
+    }
+    // not a filter key, searched as text
+    assert_eq!(Query::parse("C:/x").unwrap().text, "C:/x");
+}
+
+#[test]
+fn matches_locks_and_paths() {
+    let locks = vec![
+        lock(
+            "Content/Characters/Hero.uasset",
+            "alice",
+            "2020-11-01T10:00:00Z",
+        ),
+        lock("Content/Maps/Arena.umap", "bob", "2020-11-09T10:00:00Z"),
+    ];
+    let own_locks: HashSet<String> = vec![locks[0].id.clone()].into_iter().collect();
+    let modified: HashSet<String> = vec!["Content/Maps/Arena.umap".to_string()]
+        .into_iter()
+        .collect();
+    let context = Context {
+        locks: locks.iter().map(|l| (l.path.as_str(), l)).collect(),
+        own_locks: Some(&own_locks),
+        modified: Some(&modified),
+        now: "2020-11-10T10:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+    };
+    let files = [
+        "Content/Characters/Hero.uasset",
+        "Content/Maps/Arena.umap",
+        "Content/Props/Crate.uasset",
+    ];
+    let matching = |input: &str| -> Vec<&str> {
+        let query = Query::parse(input).unwrap();
+        files
+            .iter()
+            .copied()
+            .filter(|f| query.matches(f, &context))
+            .collect()
+    };
+
+    assert_eq!(matching("owner:bob"), vec![files[1]]);
+    assert_eq!(matching("locked:no"), vec![files[2]]);
+    assert_eq!(matching("locked:mine"), vec![files[0]]);
+    assert_eq!(matching("ext:.UASSET"), vec![files[0], files[2]]);
+    assert_eq!(matching("in:content/maps/"), vec![files[1]]);
+    assert_eq!(matching("age:>7d"), vec![files[0]]);
+    assert_eq!(matching("age:<7d"), vec![files[1]]);
+    assert_eq!(matching("status:modified"), vec![files[1]]);
+    assert_eq!(
+        matching("ext:uasset status:clean locked:yes"),
+        vec![files[0]]
+    );
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::{git, lock};
+use git_lock_manager::release;
+use git_lock_manager::runner::{CommandOutput, ProcessRunner, RecordingRunner};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[test]
+fn only_locks_with_pushed_changes_are_released() {
+    let runner = RecordingRunner::new(|command| {
+        let stdout = match command.args.last().map(String::as_str) {
+            Some("@{upstream}") if command.args[0] == "rev-parse" => "origin/main\n",
+            Some("user.email") => "alice@example.com\n",
+            // Hero was changed after it was locked, Old only before, Rock by someone else
+            Some("@{upstream}") => {
+                "\u{1}2020-11-03T10:00:00+01:00 bob@example.com\0\nContent/Rock.uasset\0\
+                 \u{1}2020-11-03T09:00:00+01:00 Alice@example.com\0\nContent/Hero.uasset\0Content/Map.umap\0\
+                 \u{1}2020-11-01T09:00:00+00:00 alice@example.com\0\nContent/Old.uasset\0"
+            }
+            Some("@{upstream}..HEAD") => "\nContent/Map.umap\0",
+            _ => " M Content/Tree.uasset\0",
+        };
+        Ok(CommandOutput {
+            success: true,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        })
+    });
+    let locks = vec![
+        lock("Content/Hero.uasset", "alice", "2020-11-02T10:00:00Z"),
+        lock("Content/Map.umap", "alice", "2020-11-02T10:00:00Z"),
+        lock("Content/Old.uasset", "alice", "2020-11-02T10:00:00Z"),
+        lock("Content/Rock.uasset", "alice", "2020-11-02T10:00:00Z"),
+        lock("Content/Tree.uasset", "alice", "2020-11-02T10:00:00Z"),
+        lock("Content/Untouched.uasset", "alice", "2020-11-02T10:00:00Z"),
+    ];
+
+    let pushed = release::pushed_locks(&runner, Path::new("/work/game"), &locks).unwrap();
+
+    let paths: Vec<&str> = pushed.iter().map(|l| l.path.as_str()).collect();
+    assert_eq!(paths, vec!["Content/Hero.uasset"]);
+    let calls = runner.calls();
+    assert_eq!(calls[2].args[4], "--since=2020-11-02T10:00:00+00:00");
+}
+
+#[test]
+fn nothing_is_released_without_an_upstream() {
+    let runner = RecordingRunner::new(|command| {
+        // the log would name Hero as pushed if it were asked
+        let (success, stdout) = match command.args.last().map(String::as_str) {
+            Some("@{upstream}") if command.args[0] == "rev-parse" => (false, ""),
+            Some("@{upstream}") => (
+                true,
+                "\u{1}2020-11-03T09:00:00+01:00\0\nContent/Hero.uasset\0",
+            ),
+            _ => (true, ""),
+        };
+        Ok(CommandOutput {
+            success,
+            stdout: stdout.to_string(),
+            stderr: "fatal: no upstream configured for branch 'main'".to_string(),
+        })
+    });
+    let locks = vec![lock("Content/Hero.uasset", "alice", "2020-11-02T10:00:00Z")];
+
+    let pushed = release::pushed_locks(&runner, Path::new("/work/game"), &locks).unwrap();
+
+    assert!(pushed.is_empty());
+    assert_eq!(runner.calls().len(), 1);
+}
+
+/// Whether the hook started `exe` within a few seconds, it runs it in the background.
+fn released(marker: &Path) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        if marker.exists() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[cfg(unix)]
+#[test]
+fn the_hook_releases_after_pushes_but_not_fetches() {
+    let remote = tempfile::tempdir().unwrap();
+    git(
+        remote.path(),
+        &["init", "-q", "--bare", "--initial-branch=main"],
+    );
+    let clone = |name: &str| {
+        let dir = tempfile::tempdir().unwrap();
+        git(
+            dir.path(),
+            &["clone", "-q", remote.path().to_str().unwrap(), "."],
+        );
+        git(dir.path(), &["config", "user.name", name]);
+        git(
+            dir.path(),
+            &["config", "user.email", &format!("{}@example.com", name)],
+        );
+        dir
+    };
+    let commit = |dir: &Path, file: &str| {
+        std::fs::write(dir.join(file), file).unwrap();
+        git(dir, &["add", file]);
+        git(dir, &["commit", "-q", "-m", file]);
+        git(dir, &["push", "-q", "origin", "HEAD:main"]);
+    };
+    let ours = clone("alice");
+    commit(ours.path(), "Hero.uasset");
+    git(ours.path(), &["fetch", "-q"]);
+    git(ours.path(), &["branch", "-q", "-u", "origin/main"]);
+    let theirs = clone("bob");
+
+    // stands in for the app, noting that it was asked to release
+    let bin = tempfile::tempdir().unwrap();
+    let marker = bin.path().join("released");
+    let exe = bin.path().join("app");
+    std::fs::write(
+        &exe,
+        format!("#!/bin/sh\necho \"$@\" > \"{}\"\n", marker.display()),
+    )
+    .unwrap();
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&exe, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    release::install_hook(&ProcessRunner::default(), ours.path(), &exe).unwrap();
+
+    commit(theirs.path(), "Map.umap");
+    git(ours.path(), &["fetch", "-q"]);
+    std::thread::sleep(Duration::from_millis(500));
+    assert!(!marker.exists(), "a fetch released locks");
+
+    git(ours.path(), &["merge", "-q", "--ff-only", "origin/main"]);
+    commit(ours.path(), "Tree.uasset");
+    assert!(released(&marker), "a push didn't release locks");
+    assert_eq!(
+        std::fs::read_to_string(&marker).unwrap().trim(),
+        "--release-pushed"
+    );
+}
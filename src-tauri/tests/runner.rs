@@ -0,0 +1,54 @@
+mod common;
+
+use backend_api::{Request, Response};
+use common::lock_json;
+use git_lock_manager::handler::{self, State};
+use git_lock_manager::runner::{CommandOutput, RecordingRunner};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn reply(success: bool, stdout: &str, stderr: &str) -> std::io::Result<CommandOutput> {
+    Ok(CommandOutput {
+        success,
+        stdout: stdout.to_string(),
+        stderr: stderr.to_string(),
+    })
+}
+
+#[test]
+fn backend_runs_git_through_runner() {
+    let runner = Arc::new(RecordingRunner::new(|command| {
+        match command.args.get(1).map(String::as_str) {
+            Some("ls-files") => reply(true, "Hero.uasset\n", ""),
+            Some("lock") => reply(true, &lock_json(7, "Hero.uasset", "alice"), ""),
+            _ => reply(true, "", ""),
+        }
+    }));
+    let state = State::new("/work/game".to_string(), runner.clone());
+
+    let response = handler::handle(
+        &state,
+        Request::LockFile {
+            path: "Hero.uasset".to_string(),
+        },
+    );
+    match response.unwrap() {
+        Response::LockFile { lock_entry } => assert_eq!(lock_entry.id, "7"),
+        r => panic!("unexpected response {:?}", r),
+    }
+
+    let calls = runner.calls();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].dir, PathBuf::from("/work/game"));
+    assert_eq!(calls[0].args, vec!["lfs", "ls-files", "-n"]);
+    assert_eq!(calls[1].args, vec!["lfs", "lock", "Hero.uasset", "--json"]);
+}
+
+#[test]
+fn failed_git_command_is_an_error() {
+    let runner = Arc::new(RecordingRunner::new(|_| reply(false, "", "Lock exists")));
+    let state = State::new(String::new(), runner);
+
+    let response = handler::handle(&state, Request::UnlockFile { id: 7 });
+    assert!(response.unwrap_err().to_string().contains("Lock exists"));
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use chrono::{DateTime, Utc};
+use common::lock;
+use git_lock_manager::stats;
+
+#[test]
+fn locks_are_counted_and_aged() {
+    let now: DateTime<Utc> = "2020-11-20T12:00:00Z".parse().unwrap();
+    let locks = vec![
+        lock(
+            "Content/Characters/Hero/Hero.uasset",
+            "alice",
+            "2020-11-20T10:00:00Z",
+        ),
+        lock(
+            "Content/Characters/Villain.uasset",
+            "bob",
+            "2020-11-19T12:00:00Z",
+        ),
+        lock("Content/Maps/Map.umap", "alice", "2020-11-01T12:00:00Z"),
+        lock("README.md", "carol", "2020-11-10T12:00:00Z"),
+        lock("Content/Tree.uasset", "bob", "not a time"),
+    ];
+
+    let stats = stats::compute(&locks, now, 7);
+
+    assert_eq!(stats.total, 5);
+    let owners: Vec<(&str, usize)> = stats
+        .per_owner
+        .iter()
+        .map(|(o, c)| (o.as_str(), *c))
+        .collect();
+    assert_eq!(owners, vec![("alice", 2), ("bob", 2), ("carol", 1)]);
+    let dirs: Vec<(&str, usize)> = stats
+        .per_directory
+        .iter()
+        .map(|(d, c)| (d.as_str(), *c))
+        .collect();
+    assert_eq!(
+        dirs,
+        vec![
+            ("Content/Characters", 2),
+            ("", 1),
+            ("Content", 1),
+            ("Content/Maps", 1)
+        ]
+    );
+    // 2h, 1d, 10d and 19d, the unreadable time is left out
+    assert_eq!(stats.median_age, Some((86400 + 10 * 86400) / 2));
+    assert_eq!(stats.longest_age, Some(19 * 86400));
+    assert_eq!(stats.oldest.unwrap().path, "Content/Maps/Map.umap");
+    let stale: Vec<&str> = stats.stale.iter().map(|l| l.path.as_str()).collect();
+    assert_eq!(stale, vec!["Content/Maps/Map.umap", "README.md"]);
+}
+
+#[test]
+fn summary_lists_every_section() {
+    let now: DateTime<Utc> = "2020-11-20T12:00:00Z".parse().unwrap();
+    let locks = vec![lock(
+        "Content/Maps/Map.umap",
+        "alice",
+        "2020-11-01T12:00:00Z",
+    )];
+
+    let summary = stats::summary(&stats::compute(&locks, now, 7), now);
+
+    assert_eq!(
+        summary,
+        "1 locks\n\
+         median age 19d 0h\n\
+         longest 19d 0h by alice, Content/Maps/Map.umap\n\
+         \n\
+         per owner\n  alice  1\n\
+         \n\
+         per directory\n  Content/Maps  1\n\
+         \n\
+         1 older than 7 days\n  19d 0h  alice  Content/Maps/Map.umap\n"
+    );
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::lock;
+use git_lock_manager::tree;
+
+#[test]
+fn children_count_locks_below_folders() {
+    let files: Vec<String> = vec![
+        "Content/Characters/Hero.uasset",
+        "Content/Characters/Villain.uasset",
+        "Content/Maps/Arena.umap",
+        "Content/Splash.png",
+        "Readme.pdf",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    let locks = vec![
+        lock("Content/Characters/Hero.uasset", "alice", ""),
+        lock("Content/Characters/Villain.uasset", "bob", ""),
+        lock("Content/Maps/Arena.umap", "bob", ""),
+    ];
+
+    let root = tree::children(&files, &locks, "");
+    assert_eq!(root.len(), 2);
+    assert_eq!((root[0].name.as_str(), root[0].is_dir), ("Content", true));
+    assert_eq!((root[0].files, root[0].locked), (4, 3));
+    assert_eq!(
+        root[0].owners,
+        vec![("bob".to_string(), 2), ("alice".to_string(), 1)]
+    );
+    assert_eq!(
+        (root[1].name.as_str(), root[1].is_dir),
+        ("Readme.pdf", false)
+    );
+
+    let content = tree::children(&files, &locks, "Content/");
+    let names: Vec<&str> = content.iter().map(|n| n.path.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["Content/Characters", "Content/Maps", "Content/Splash.png"]
+    );
+    assert_eq!(content[1].lock, None);
+    assert_eq!(
+        tree::children(&files, &locks, "Content/Maps")[0].lock,
+        Some(locks[2].clone())
+    );
+}